@@ -0,0 +1,25 @@
+//! Wrappers that change how an [`AudioFile`](crate::AudioFile) is played back
+
+mod clip;
+mod concat;
+mod decimate;
+mod fade;
+mod limiter;
+mod looping;
+mod low_pass;
+mod mid_side;
+mod mixer;
+mod pad;
+mod polyphase;
+
+pub use clip::Clip;
+pub use concat::Concat;
+pub use decimate::Decimate;
+pub use fade::Fade;
+pub use limiter::Limiter;
+pub use looping::Loop;
+pub use low_pass::LowPass;
+pub use mid_side::{LeftRight, MidSide};
+pub use mixer::Mixer;
+pub use pad::Pad;
+pub use polyphase::{MAX_UPSAMPLE_FACTOR, PolyphaseResampler};
@@ -0,0 +1,122 @@
+use core::marker::PhantomData;
+
+use crate::{AudioFile, Channels, PlatformFile, SampleFormat};
+
+/// Plays only the frames `start..end` of a source
+pub struct Clip<File: PlatformFile, A: AudioFile<File>> {
+    source: A,
+    start: usize,
+    end: usize,
+    _file: PhantomData<File>,
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> Clip<File, A> {
+    /// seeks the source to `start`, `end` is clamped to the length of the source
+    pub fn new(source: A, start: usize, end: usize) -> Result<Self, A::Error> {
+        let end = end.min(source.total_frames());
+        let mut clip = Self {
+            source,
+            start: start.min(end),
+            end,
+            _file: PhantomData,
+        };
+        clip.restart()?;
+        Ok(clip)
+    }
+
+    /// get back the source
+    pub fn into_inner(self) -> A {
+        self.source
+    }
+
+    fn channel_count(&self) -> usize {
+        self.source.channels().count() as usize
+    }
+
+    /// frame the source is currently at
+    fn frame(&self) -> usize {
+        self.source.played() / self.channel_count()
+    }
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> AudioFile<File> for Clip<File, A> {
+    type Error = A::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let frames_left = self.end.saturating_sub(self.frame());
+        let to_read = buf.len().min(frames_left * self.source.frame_size());
+        self.source.read(&mut buf[..to_read])
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.source.sample_rate()
+    }
+
+    fn channels(&self) -> Channels {
+        self.source.channels()
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.source.sample_format()
+    }
+
+    /// seeks are clamped to the bounds of the clip
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
+        let channels = self.channel_count() as i64;
+        let played = self.source.played() as i64;
+        let target = (played + sample_offset)
+            .clamp(self.start as i64 * channels, self.end as i64 * channels);
+        self.source.try_seek(target - played)
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.source.is_seekable()
+    }
+
+    fn played(&self) -> usize {
+        self.source
+            .played()
+            .saturating_sub(self.start * self.channel_count())
+    }
+
+    fn data_len(&self) -> usize {
+        (self.end - self.start) * self.source.frame_size()
+    }
+
+    /// go back to the first frame of the clip
+    fn restart(&mut self) -> Result<(), Self::Error> {
+        let start = (self.start * self.channel_count()) as i64;
+        self.source.try_seek(start - self.source.played() as i64)
+    }
+
+    fn is_eof(&self) -> bool {
+        self.frame() >= self.end || self.source.is_eof()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Clip;
+    use crate::{AudioFile, MONO_16BIT, TestFile, wav::Wav};
+
+    #[test]
+    fn clip_frames() {
+        let wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        let mut clip = Clip::new(wav, 1, 3).unwrap();
+        assert!(clip.data_len() == 4);
+        assert!(clip.played() == 0);
+
+        let mut samples = [0_u8; 8];
+        assert!(clip.read(&mut samples) == Ok(4));
+        assert!(samples[..4] == [0xfe, 0xff, 0x02, 0x00]);
+        assert!(clip.is_eof());
+        assert!(clip.read(&mut samples) == Ok(0));
+
+        clip.restart().unwrap();
+        assert!(!clip.is_eof());
+        let mut sample = [0_u8; 2];
+        clip.read(&mut sample).unwrap();
+        assert!(sample == [0xfe, 0xff]);
+        assert!(clip.played() == 1);
+    }
+}
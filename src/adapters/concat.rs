@@ -0,0 +1,155 @@
+use core::marker::PhantomData;
+
+use crate::{AudioFile, Channels, Error, PlatformFile, SampleFormat};
+
+/// Plays several sources with the same format back to back as one gapless stream
+pub struct Concat<File: PlatformFile, A: AudioFile<File>, const N: usize> {
+    sources: [A; N],
+    current: usize,
+    _file: PhantomData<File>,
+}
+
+impl<File: PlatformFile, A: AudioFile<File>, const N: usize> Concat<File, A, N> {
+    /// all sources must share the same sample rate, channels and sample format
+    pub fn new(sources: [A; N]) -> Result<Self, Error> {
+        const { assert!(N > 0, "Concat needs at least one source") };
+
+        let first = &sources[0];
+        if sources.iter().any(|source| {
+            source.sample_rate() != first.sample_rate()
+                || source.channels() != first.channels()
+                || source.sample_format() != first.sample_format()
+        }) {
+            return Err(Error::FormatMismatch);
+        }
+
+        Ok(Self {
+            sources,
+            current: 0,
+            _file: PhantomData,
+        })
+    }
+
+    /// get back the sources
+    pub fn into_inner(self) -> [A; N] {
+        self.sources
+    }
+}
+
+impl<File: PlatformFile, A: AudioFile<File>, const N: usize> AudioFile<File>
+    for Concat<File, A, N>
+{
+    type Error = A::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        loop {
+            let len = self.sources[self.current].read(buf)?;
+            // a short buffer can read 0 without the source being finished
+            if len > 0 || !self.sources[self.current].is_eof() || self.current + 1 == N {
+                return Ok(len);
+            }
+            self.current += 1;
+        }
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.sources[0].sample_rate()
+    }
+
+    fn channels(&self) -> Channels {
+        self.sources[0].channels()
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.sources[0].sample_format()
+    }
+
+    /// seeking is limited to the source currently being played
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
+        self.sources[self.current].try_seek(sample_offset)
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.sources.iter().all(A::is_seekable)
+    }
+
+    fn played(&self) -> usize {
+        self.sources[..=self.current]
+            .iter()
+            .map(|source| source.played())
+            .sum()
+    }
+
+    fn data_len(&self) -> usize {
+        self.sources.iter().map(|source| source.data_len()).sum()
+    }
+
+    fn restart(&mut self) -> Result<(), Self::Error> {
+        for source in self.sources[..=self.current].iter_mut() {
+            source.restart()?;
+        }
+        self.current = 0;
+        Ok(())
+    }
+
+    fn is_eof(&self) -> bool {
+        self.current + 1 == N && self.sources[self.current].is_eof()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Concat;
+    use crate::{AudioFile, Error, MONO_16BIT, STEREO_8BIT, TestFile, wav::Wav};
+
+    #[test]
+    fn concat_two_sources() {
+        let mut concat = Concat::new([
+            Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap(),
+            Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap(),
+        ])
+        .unwrap();
+        assert!(concat.data_len() == 16);
+
+        let mut sample = [0_u8; 2];
+        for _ in 0..2 {
+            concat.read(&mut sample).unwrap();
+            assert!(sample == [0x01, 0x00]);
+            concat.read(&mut sample).unwrap();
+            assert!(sample == [0xfe, 0xff]);
+            concat.read(&mut sample).unwrap();
+            assert!(sample == [0x02, 0x00]);
+            assert!(!concat.is_eof());
+            concat.read(&mut sample).unwrap();
+            assert!(sample == [0xff, 0xff]);
+        }
+
+        assert!(concat.is_eof());
+        assert!(concat.read(&mut sample) == Ok(0));
+    }
+
+    #[test]
+    fn concat_sub_frame_read() {
+        let mut concat = Concat::new([
+            Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap(),
+            Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap(),
+        ])
+        .unwrap();
+
+        assert!(concat.read(&mut [0; 1]) == Ok(0));
+        assert!(concat.played() == 0);
+
+        let mut samples = [0_i16; 8];
+        assert!(concat.read_samples(&mut samples) == Ok(8));
+        assert!(samples == [1, -2, 2, -1, 1, -2, 2, -1]);
+    }
+
+    #[test]
+    fn concat_format_mismatch() {
+        let concat = Concat::new([
+            Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap(),
+            Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap(),
+        ]);
+        assert!(matches!(concat, Err(Error::FormatMismatch)));
+    }
+}
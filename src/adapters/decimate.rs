@@ -0,0 +1,165 @@
+use core::marker::PhantomData;
+
+use crate::{AudioFile, Channels, MAX_CHANNELS, PlatformFile, SampleFormat};
+
+/// Downsamples a source by an integer factor, keeping every `factor`th frame
+pub struct Decimate<File: PlatformFile, A: AudioFile<File>> {
+    source: A,
+    factor: u16,
+    box_filter: bool,
+    _file: PhantomData<File>,
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> Decimate<File, A> {
+    /// panics if `factor` is 0
+    pub fn new(source: A, factor: u16) -> Self {
+        assert!(factor > 0, "decimation factor must be at least 1");
+        Self {
+            source,
+            factor,
+            box_filter: false,
+            _file: PhantomData,
+        }
+    }
+
+    /// average each group of `factor` frames instead of keeping the first, to reduce aliasing
+    pub fn with_box_filter(mut self) -> Self {
+        self.box_filter = true;
+        self
+    }
+
+    /// get back the source
+    pub fn into_inner(self) -> A {
+        self.source
+    }
+
+    fn channel_count(&self) -> usize {
+        self.source.channels().count() as usize
+    }
+
+    /// read the next group of frames from the source into `out`, false once the source ended
+    fn read_group(&mut self, out: &mut [u8]) -> Result<bool, A::Error> {
+        let format = self.source.sample_format();
+        let sample_size = format.size() as usize;
+
+        let mut sums = [0_i64; MAX_CHANNELS as usize];
+        let mut frame = [0_u8; 64];
+        let mut frames = 0;
+        while frames < self.factor && self.source.read_frame(&mut frame)? {
+            if frames == 0 {
+                out.copy_from_slice(&frame[..out.len()]);
+            }
+            for (sum, sample) in sums
+                .iter_mut()
+                .zip(frame[..out.len()].chunks_exact(sample_size))
+            {
+                *sum += format.to_i32(sample) as i64;
+            }
+            frames += 1;
+        }
+
+        if self.box_filter && frames > 0 {
+            for (sum, sample) in sums.iter().zip(out.chunks_exact_mut(sample_size)) {
+                format.write_i32((sum / frames as i64) as i32, sample);
+            }
+        }
+        Ok(frames > 0)
+    }
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> AudioFile<File> for Decimate<File, A> {
+    type Error = A::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let frame_size = self.frame_size();
+        let mut read = 0;
+        for out in buf.chunks_exact_mut(frame_size) {
+            if !self.read_group(out)? {
+                break;
+            }
+            read += frame_size;
+        }
+        Ok(read)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.source.sample_rate() / self.factor
+    }
+
+    fn channels(&self) -> Channels {
+        self.source.channels()
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.source.sample_format()
+    }
+
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
+        self.source.try_seek(sample_offset * self.factor as i64)
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.source.is_seekable()
+    }
+
+    fn played(&self) -> usize {
+        let frames = self.source.played() / self.channel_count();
+        frames.div_ceil(self.factor as usize) * self.channel_count()
+    }
+
+    fn data_len(&self) -> usize {
+        self.source.total_frames().div_ceil(self.factor as usize) * self.frame_size()
+    }
+
+    fn restart(&mut self) -> Result<(), Self::Error> {
+        self.source.restart()
+    }
+
+    fn is_eof(&self) -> bool {
+        self.source.is_eof()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Decimate;
+    use crate::{AudioFile, MONO_16BIT, STEREO_8BIT, TestFile, wav::Wav};
+
+    #[test]
+    fn decimate_by_two() {
+        let wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        let mut decimate = Decimate::new(wav, 2);
+        assert!(decimate.sample_rate() == 4_000);
+        assert!(decimate.total_frames() == 2);
+
+        let mut samples = [0_u8; 8];
+        assert!(decimate.read(&mut samples) == Ok(4));
+        assert!(samples[..4] == [0x01, 0x00, 0x02, 0x00]);
+        assert!(decimate.played() == 2);
+        assert!(decimate.is_eof());
+
+        let wav = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        let mut decimate = Decimate::new(wav, 2);
+        assert!(decimate.read(&mut samples) == Ok(4));
+        assert!(
+            samples[..4]
+                == [
+                    STEREO_8BIT[44],
+                    STEREO_8BIT[45],
+                    STEREO_8BIT[48],
+                    STEREO_8BIT[49]
+                ]
+        );
+    }
+
+    #[test]
+    fn box_filter() {
+        let wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        let mut decimate = Decimate::new(wav, 2).with_box_filter();
+
+        // (1 + -2) / 2 and (2 + -1) / 2, rounded down
+        let mut samples = [0_u8; 4];
+        assert!(decimate.read(&mut samples) == Ok(4));
+        assert!(samples == [0xff, 0xff, 0x00, 0x00]);
+    }
+}
@@ -0,0 +1,159 @@
+use core::marker::PhantomData;
+
+use crate::{AudioFile, Channels, Error, PlatformFile, Q15, SampleFormat, convert};
+
+/// Ramps a source linearly up from silence over its first frames and back down over its last
+///
+/// the envelope follows the source position, so it starts over on a restart or seek
+pub struct Fade<File: PlatformFile, A: AudioFile<File>> {
+    source: A,
+    fade_in: usize,
+    fade_out: usize,
+    _file: PhantomData<File>,
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> Fade<File, A> {
+    /// fade in over `fade_in` frames and out over the last `fade_out` frames
+    ///
+    /// a fade out needs the length of the source, a source reporting no frames returns
+    /// [`Error::UnknownLength`]
+    pub fn new(source: A, fade_in: usize, fade_out: usize) -> Result<Self, Error> {
+        if fade_out > 0 && source.total_frames() == 0 {
+            return Err(Error::UnknownLength);
+        }
+        Ok(Self {
+            source,
+            fade_in,
+            fade_out,
+            _file: PhantomData,
+        })
+    }
+
+    /// get back the source
+    pub fn into_inner(self) -> A {
+        self.source
+    }
+
+    /// envelope at `frame`, `None` outside both ramps
+    fn gain(&self, frame: usize) -> Option<Q15> {
+        let ramp = |step: usize, len: usize| (step * 0x8000 / len) as i16;
+        let fade_in = (frame < self.fade_in).then(|| ramp(frame, self.fade_in));
+        let left = self.source.total_frames().saturating_sub(frame + 1);
+        let fade_out = (left < self.fade_out).then(|| ramp(left, self.fade_out));
+        match (fade_in, fade_out) {
+            (Some(a), Some(b)) => Some(Q15(a.min(b))),
+            (a, b) => a.or(b).map(Q15),
+        }
+    }
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> AudioFile<File> for Fade<File, A> {
+    type Error = A::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let format = self.source.sample_format();
+        let sample_size = format.size() as usize;
+        let channels = self.source.channels().count() as usize;
+        let first = self.source.played();
+
+        let len = self.source.read(buf)?;
+        for (i, sample) in buf[..len].chunks_exact_mut(sample_size).enumerate() {
+            if let Some(gain) = self.gain((first + i) / channels) {
+                format.write_i32(convert::scale(format.to_i32(sample), gain), sample);
+            }
+        }
+        Ok(len)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.source.sample_rate()
+    }
+
+    fn channels(&self) -> Channels {
+        self.source.channels()
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.source.sample_format()
+    }
+
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
+        self.source.try_seek(sample_offset)
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.source.is_seekable()
+    }
+
+    fn played(&self) -> usize {
+        self.source.played()
+    }
+
+    fn data_len(&self) -> usize {
+        self.source.data_len()
+    }
+
+    fn restart(&mut self) -> Result<(), Self::Error> {
+        self.source.restart()
+    }
+
+    fn is_eof(&self) -> bool {
+        self.source.is_eof()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Fade;
+    use crate::{AudioFile, Error, TestFile, wav::Wav};
+
+    #[test]
+    fn ramps_ends() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x30, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x0c, 0x00, 0x00, 0x00, // data chunk size
+            0x00, 0x40, 0x00, 0x40, 0x00, 0x40, // frames 1 to 3
+            0x00, 0x40, 0x00, 0x40, 0x00, 0x40, // frames 4 to 6
+        ]);
+        let wav = Wav::new(file).unwrap();
+        let mut fade = Fade::new(wav, 2, 2).unwrap();
+
+        let mut samples = [0_i16; 6];
+        assert!(fade.read_samples(&mut samples) == Ok(6));
+        assert!(samples == [0, 0x2000, 0x4000, 0x4000, 0x2000, 0]);
+
+        // the envelope starts over
+        fade.restart().unwrap();
+        assert!(fade.read_samples(&mut samples[..1]) == Ok(1));
+        assert!(samples[0] == 0);
+
+        let empty = Wav::new(TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x24, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x00, 0x00, 0x00, 0x00, // data chunk size
+        ]))
+        .unwrap();
+        assert!(matches!(Fade::new(empty, 0, 2), Err(Error::UnknownLength)));
+    }
+}
@@ -0,0 +1,120 @@
+use core::marker::PhantomData;
+
+use crate::{AudioFile, Channels, PlatformFile, SampleFormat};
+
+/// Hard clips every sample of a source to plus or minus a ceiling, so overdriven content
+/// never reaches the DAC above it
+///
+/// there's no lookahead or release, samples over the ceiling are simply clamped
+pub struct Limiter<File: PlatformFile, A: AudioFile<File>> {
+    source: A,
+    /// ceiling scaled to the full i32 range
+    limit: i32,
+    _file: PhantomData<File>,
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> Limiter<File, A> {
+    /// `ceiling` is in the 16 bit sample range and scaled to match other formats, so 16_384
+    /// limits to half of full scale. Panics if it's negative
+    pub fn new(source: A, ceiling: i16) -> Self {
+        assert!(ceiling >= 0, "the ceiling must not be negative");
+        Self {
+            source,
+            limit: (ceiling as i32) << 16,
+            _file: PhantomData,
+        }
+    }
+
+    /// get back the source
+    pub fn into_inner(self) -> A {
+        self.source
+    }
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> AudioFile<File> for Limiter<File, A> {
+    type Error = A::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let format = self.source.sample_format();
+        let len = self.source.read(buf)?;
+        for sample in buf[..len].chunks_exact_mut(format.size() as usize) {
+            let value = format.to_i32(sample);
+            let limited = value.clamp(-self.limit, self.limit);
+            if limited != value {
+                format.write_i32(limited, sample);
+            }
+        }
+        Ok(len)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.source.sample_rate()
+    }
+
+    fn channels(&self) -> Channels {
+        self.source.channels()
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.source.sample_format()
+    }
+
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
+        self.source.try_seek(sample_offset)
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.source.is_seekable()
+    }
+
+    fn played(&self) -> usize {
+        self.source.played()
+    }
+
+    fn data_len(&self) -> usize {
+        self.source.data_len()
+    }
+
+    fn restart(&mut self) -> Result<(), Self::Error> {
+        self.source.restart()
+    }
+
+    fn is_eof(&self) -> bool {
+        self.source.is_eof()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Limiter;
+    use crate::{AudioFile, TestFile, wav::Wav};
+
+    #[test]
+    fn clamps_full_scale() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x2c, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x08, 0x00, 0x00, 0x00, // data chunk size
+            0xff, 0x7f, // full scale
+            0x00, 0x80, // negative full scale
+            0x00, 0x30, // below the ceiling
+            0x00, 0xc0, // -0x4000, on the ceiling
+        ]);
+        let wav = Wav::new(file).unwrap();
+        let mut limiter = Limiter::new(wav, 0x4000);
+
+        let mut samples = [0_i16; 4];
+        assert!(limiter.read_samples(&mut samples) == Ok(4));
+        assert!(samples == [0x4000, -0x4000, 0x3000, -0x4000]);
+    }
+}
@@ -0,0 +1,104 @@
+use core::marker::PhantomData;
+
+use crate::{AudioFile, Channels, Error, PlatformFile, SampleFormat};
+
+/// Plays a source over and over, starting again from its first sample at EOF
+pub struct Loop<File: PlatformFile, A: AudioFile<File>> {
+    source: A,
+    _file: PhantomData<File>,
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> Loop<File, A> {
+    /// the source has to seek back to its start, otherwise [`Error::SeekUnsupported`] is
+    /// returned
+    pub fn new(source: A) -> Result<Self, Error> {
+        if !source.is_seekable() {
+            return Err(Error::SeekUnsupported);
+        }
+        Ok(Self {
+            source,
+            _file: PhantomData,
+        })
+    }
+
+    /// get back the source
+    pub fn into_inner(self) -> A {
+        self.source
+    }
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> AudioFile<File> for Loop<File, A> {
+    type Error = A::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = self.source.read(buf)?;
+        if len > 0 || self.is_eof() || !self.source.is_eof() {
+            return Ok(len);
+        }
+        self.source.restart()?;
+        self.source.read(buf)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.source.sample_rate()
+    }
+
+    fn channels(&self) -> Channels {
+        self.source.channels()
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.source.sample_format()
+    }
+
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
+        self.source.try_seek(sample_offset)
+    }
+
+    /// a loop that can't rewind its source can't seek either
+    fn is_seekable(&self) -> bool {
+        self.source.is_seekable()
+    }
+
+    /// position within the current pass
+    fn played(&self) -> usize {
+        self.source.played()
+    }
+
+    /// length of a single pass
+    fn data_len(&self) -> usize {
+        self.source.data_len()
+    }
+
+    /// only an empty source ends
+    fn is_eof(&self) -> bool {
+        self.source.data_len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Loop;
+    use crate::{AudioFile, Error, MONO_16BIT, TestFile, test_files::StreamFile, wav::Wav};
+
+    #[test]
+    fn wraps_around() {
+        let wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(wav.is_seekable());
+        let mut looped = Loop::new(wav).unwrap();
+        assert!(looped.is_seekable());
+
+        let mut samples = [0_i16; 6];
+        assert!(looped.read_samples(&mut samples) == Ok(6));
+        assert!(samples == [1, -2, 2, -1, 1, -2]);
+        assert!(looped.played() == 2);
+        assert!(!looped.is_eof());
+    }
+
+    #[test]
+    fn rejects_streams() {
+        let wav = Wav::new_streaming(StreamFile(TestFile::from_bytes(MONO_16BIT))).unwrap();
+        assert!(!wav.is_seekable());
+        assert!(matches!(Loop::new(wav), Err(Error::SeekUnsupported)));
+    }
+}
@@ -0,0 +1,144 @@
+use core::marker::PhantomData;
+
+use crate::{AudioFile, Channels, MAX_CHANNELS, PlatformFile, Q15, SampleFormat};
+
+/// Smooths a source with a one pole low-pass filter, `y[n] = y[n-1] + a * (x[n] - y[n-1])`
+///
+/// a coefficient near 1.0 passes almost everything, smaller ones lower the cutoff
+pub struct LowPass<File: PlatformFile, A: AudioFile<File>> {
+    source: A,
+    coefficient: Q15,
+    /// last output of each channel
+    state: [i32; MAX_CHANNELS as usize],
+    _file: PhantomData<File>,
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> LowPass<File, A> {
+    pub fn new(source: A, coefficient: Q15) -> Self {
+        Self {
+            source,
+            coefficient,
+            state: [0; MAX_CHANNELS as usize],
+            _file: PhantomData,
+        }
+    }
+
+    /// get back the source
+    pub fn into_inner(self) -> A {
+        self.source
+    }
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> AudioFile<File> for LowPass<File, A> {
+    type Error = A::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let format = self.source.sample_format();
+        let sample_size = format.size() as usize;
+        let channels = self.source.channels().count() as usize;
+        let first = self.source.played() % channels;
+
+        let len = self.source.read(buf)?;
+        for (i, sample) in buf[..len].chunks_exact_mut(sample_size).enumerate() {
+            let y = &mut self.state[(first + i) % channels];
+            let x = format.to_i32(sample);
+            *y += (((x as i64 - *y as i64) * self.coefficient.0 as i64) >> 15) as i32;
+            format.write_i32(*y, sample);
+        }
+        Ok(len)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.source.sample_rate()
+    }
+
+    fn channels(&self) -> Channels {
+        self.source.channels()
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.source.sample_format()
+    }
+
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
+        self.state = [0; MAX_CHANNELS as usize];
+        self.source.try_seek(sample_offset)
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.source.is_seekable()
+    }
+
+    fn played(&self) -> usize {
+        self.source.played()
+    }
+
+    fn data_len(&self) -> usize {
+        self.source.data_len()
+    }
+
+    fn restart(&mut self) -> Result<(), Self::Error> {
+        self.state = [0; MAX_CHANNELS as usize];
+        self.source.restart()
+    }
+
+    fn is_eof(&self) -> bool {
+        self.source.is_eof()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LowPass;
+    use crate::{AudioFile, Q15, TestFile, wav::Wav};
+
+    #[test]
+    fn step_response() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x2c, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x02, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x00, 0x7d, 0x00, 0x00, // byte rate
+            0x04, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x10, 0x00, 0x00, 0x00, // data chunk size
+            0x00, 0x40, 0x00, 0xc0, // frame 1
+            0x00, 0x40, 0x00, 0xc0, // frame 2
+            0x00, 0x40, 0x00, 0xc0, // frame 3
+            0x00, 0x40, 0x00, 0xc0, // frame 4
+        ]);
+        let wav = Wav::new(file).unwrap();
+        // a = 0.5 halves the distance to the input every frame
+        let mut low_pass = LowPass::new(wav, Q15(0x4000));
+
+        let mut samples = [0_u8; 4];
+        let mut outputs = [(0, 0); 4];
+        for output in outputs.iter_mut() {
+            assert!(low_pass.read(&mut samples) == Ok(4));
+            *output = (
+                i16::from_le_bytes([samples[0], samples[1]]),
+                i16::from_le_bytes([samples[2], samples[3]]),
+            );
+        }
+        assert!(
+            outputs
+                == [
+                    (8192, -8192),
+                    (12288, -12288),
+                    (14336, -14336),
+                    (15360, -15360)
+                ]
+        );
+
+        // the state doesn't carry over a restart
+        low_pass.restart().unwrap();
+        assert!(low_pass.read(&mut samples) == Ok(4));
+        assert!(samples == [0x00, 0x20, 0x00, 0xe0]);
+    }
+}
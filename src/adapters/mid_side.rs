@@ -0,0 +1,203 @@
+use core::marker::PhantomData;
+
+use crate::{AudioFile, Channels, Error, PlatformFile, SampleFormat};
+
+/// Encodes a stereo source as mid `(L + R) / 2` and side `(L - R) / 2` frames
+pub struct MidSide<File: PlatformFile, A: AudioFile<File>> {
+    source: A,
+    _file: PhantomData<File>,
+}
+
+/// Decodes a mid/side source back to left `M + S` and right `M - S` frames, saturating
+pub struct LeftRight<File: PlatformFile, A: AudioFile<File>> {
+    source: A,
+    _file: PhantomData<File>,
+}
+
+/// read whole frames from a stereo `source` and replace each sample pair with `matrix(a, b)`
+fn read_matrixed<File: PlatformFile, A: AudioFile<File>>(
+    source: &mut A,
+    buf: &mut [u8],
+    matrix: fn(i32, i32) -> (i32, i32),
+) -> Result<usize, A::Error> {
+    let format = source.sample_format();
+    let sample_size = format.size() as usize;
+    let frame_size = source.frame_size();
+
+    let to_read = buf.len() - buf.len() % frame_size;
+    let len = source.read(&mut buf[..to_read])?;
+    for frame in buf[..len].chunks_exact_mut(frame_size) {
+        let (first, second) = frame.split_at_mut(sample_size);
+        let (a, b) = matrix(format.to_i32(first), format.to_i32(second));
+        format.write_i32(a, first);
+        format.write_i32(b, second);
+    }
+    Ok(len)
+}
+
+fn mid_side(l: i32, r: i32) -> (i32, i32) {
+    let (l, r) = (l as i64, r as i64);
+    (((l + r) / 2) as i32, ((l - r) / 2) as i32)
+}
+
+fn left_right(m: i32, s: i32) -> (i32, i32) {
+    (m.saturating_add(s), m.saturating_sub(s))
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> MidSide<File, A> {
+    /// the source must be stereo
+    pub fn new(source: A) -> Result<Self, Error> {
+        if source.channels() != Channels::Stereo {
+            return Err(Error::UnsupportedChannelCount);
+        }
+        Ok(Self {
+            source,
+            _file: PhantomData,
+        })
+    }
+
+    /// get back the source
+    pub fn into_inner(self) -> A {
+        self.source
+    }
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> AudioFile<File> for MidSide<File, A> {
+    type Error = A::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        read_matrixed(&mut self.source, buf, mid_side)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.source.sample_rate()
+    }
+
+    fn channels(&self) -> Channels {
+        self.source.channels()
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.source.sample_format()
+    }
+
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
+        self.source.try_seek(sample_offset)
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.source.is_seekable()
+    }
+
+    fn played(&self) -> usize {
+        self.source.played()
+    }
+
+    fn data_len(&self) -> usize {
+        self.source.data_len()
+    }
+
+    fn restart(&mut self) -> Result<(), Self::Error> {
+        self.source.restart()
+    }
+
+    fn is_eof(&self) -> bool {
+        self.source.is_eof()
+    }
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> LeftRight<File, A> {
+    /// the source must be stereo
+    pub fn new(source: A) -> Result<Self, Error> {
+        if source.channels() != Channels::Stereo {
+            return Err(Error::UnsupportedChannelCount);
+        }
+        Ok(Self {
+            source,
+            _file: PhantomData,
+        })
+    }
+
+    /// get back the source
+    pub fn into_inner(self) -> A {
+        self.source
+    }
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> AudioFile<File> for LeftRight<File, A> {
+    type Error = A::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        read_matrixed(&mut self.source, buf, left_right)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.source.sample_rate()
+    }
+
+    fn channels(&self) -> Channels {
+        self.source.channels()
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.source.sample_format()
+    }
+
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
+        self.source.try_seek(sample_offset)
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.source.is_seekable()
+    }
+
+    fn played(&self) -> usize {
+        self.source.played()
+    }
+
+    fn data_len(&self) -> usize {
+        self.source.data_len()
+    }
+
+    fn restart(&mut self) -> Result<(), Self::Error> {
+        self.source.restart()
+    }
+
+    fn is_eof(&self) -> bool {
+        self.source.is_eof()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LeftRight, MidSide};
+    use crate::{AudioFile, Error, MONO_16BIT, STEREO_8BIT, TestFile, wav::Wav};
+
+    #[test]
+    fn mid_side() {
+        let wav = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        let mut mid_side = MidSide::new(wav).unwrap();
+
+        // M/S of the L/R frames (1, 0), (254, 255), (2, 0), (255, 255) rounded down to 8 bit
+        let mut samples = [0_i16; 8];
+        assert!(mid_side.read_samples_i16(&mut samples) == Ok(8));
+        assert!(samples == [-32768, 0, 32256, -256, -32512, 256, 32512, 0]);
+
+        let wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(matches!(
+            MidSide::new(wav),
+            Err(Error::UnsupportedChannelCount)
+        ));
+    }
+
+    #[test]
+    fn left_right_saturates() {
+        let wav = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        let mut left_right = LeftRight::new(wav).unwrap();
+
+        // every left sample saturates
+        let mut samples = [0_i16; 8];
+        assert!(left_right.read_samples_i16(&mut samples) == Ok(8));
+        assert!(samples == [-32768, 256, 32512, -256, -32768, 512, 32512, 0]);
+    }
+}
@@ -0,0 +1,199 @@
+use core::marker::PhantomData;
+
+use crate::{AudioFile, Channels, Error, PlatformFile, SampleFormat};
+
+/// Mixes a second source over a primary one with saturating addition
+///
+/// Playback follows the primary source `a`, the overlay `b` ends naturally. If `a` ends first
+/// the rest of `b` is never played.
+pub struct Mixer<File, A, B>
+where
+    File: PlatformFile,
+    A: AudioFile<File>,
+    B: AudioFile<File, Error = A::Error>,
+{
+    a: A,
+    b: B,
+    start_frame: usize,
+    _file: PhantomData<File>,
+}
+
+impl<File, A, B> Mixer<File, A, B>
+where
+    File: PlatformFile,
+    A: AudioFile<File>,
+    B: AudioFile<File, Error = A::Error>,
+{
+    /// mix `b` into `a` from the first frame
+    pub fn new(a: A, b: B) -> Result<Self, Error> {
+        Self::overlay_at(a, b, 0)
+    }
+
+    /// play `a` alone until `start_frame`, then mix in `b` until it ends
+    ///
+    /// both sources must share the same sample rate, channels and sample format
+    pub fn overlay_at(a: A, b: B, start_frame: usize) -> Result<Self, Error> {
+        if a.sample_rate() != b.sample_rate()
+            || a.channels() != b.channels()
+            || a.sample_format() != b.sample_format()
+        {
+            return Err(Error::FormatMismatch);
+        }
+
+        Ok(Self {
+            a,
+            b,
+            start_frame,
+            _file: PhantomData,
+        })
+    }
+
+    /// get back both sources
+    pub fn into_inner(self) -> (A, B) {
+        (self.a, self.b)
+    }
+
+    /// frame the primary source is currently at
+    fn frame(&self) -> usize {
+        self.a.played() / self.a.channels().count() as usize
+    }
+
+    /// add the overlay onto `buf`, which was just read from the primary source
+    fn mix_overlay(&mut self, buf: &mut [u8]) -> Result<(), A::Error> {
+        let format = self.a.sample_format();
+        let sample_size = format.size() as usize;
+        let frame_size = self.a.frame_size();
+
+        let mut scratch = [0_u8; 64];
+        let chunk_len = scratch.len() - scratch.len() % frame_size;
+        let mut mixed = 0;
+        while mixed < buf.len() {
+            let want = (buf.len() - mixed).min(chunk_len);
+            let len = self.b.read(&mut scratch[..want])?;
+            if len == 0 {
+                break;
+            }
+            for (out, overlay) in buf[mixed..mixed + len]
+                .chunks_exact_mut(sample_size)
+                .zip(scratch[..len].chunks_exact(sample_size))
+            {
+                let sample = format.to_i32(out).saturating_add(format.to_i32(overlay));
+                format.write_i32(sample, out);
+            }
+            mixed += len;
+        }
+        Ok(())
+    }
+}
+
+impl<File, A, B> AudioFile<File> for Mixer<File, A, B>
+where
+    File: PlatformFile,
+    A: AudioFile<File>,
+    B: AudioFile<File, Error = A::Error>,
+{
+    type Error = A::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let frame = self.frame();
+        if frame < self.start_frame {
+            // stop at the overlay start so it lines up with the right frame
+            let to_read = buf
+                .len()
+                .min((self.start_frame - frame) * self.a.frame_size());
+            return self.a.read(&mut buf[..to_read]);
+        }
+
+        let len = self.a.read(buf)?;
+        self.mix_overlay(&mut buf[..len])?;
+        Ok(len)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.a.sample_rate()
+    }
+
+    fn channels(&self) -> Channels {
+        self.a.channels()
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.a.sample_format()
+    }
+
+    /// seeks the primary source and moves the overlay to the matching frame
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
+        self.a.try_seek(sample_offset)?;
+
+        let channels = self.a.channels().count() as usize;
+        let overlay_frame = self.frame().saturating_sub(self.start_frame);
+        let overlay_frames = self.b.data_len() / self.b.frame_size();
+        let target = overlay_frame.min(overlay_frames) * channels;
+        self.b.try_seek(target as i64 - self.b.played() as i64)
+    }
+
+    fn restart(&mut self) -> Result<(), Self::Error> {
+        self.a.restart()?;
+        self.b.restart()
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.a.is_seekable() && self.b.is_seekable()
+    }
+
+    fn played(&self) -> usize {
+        self.a.played()
+    }
+
+    fn data_len(&self) -> usize {
+        self.a.data_len()
+    }
+
+    fn is_eof(&self) -> bool {
+        self.a.is_eof()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mixer;
+    use crate::{AudioFile, Error, MONO_16BIT, STEREO_8BIT, TestFile, adapters::Concat, wav::Wav};
+
+    #[test]
+    fn overlay_at_frame() {
+        let background = Concat::new([
+            Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap(),
+            Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap(),
+        ])
+        .unwrap();
+        let click = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        let mut mixer = Mixer::overlay_at(background, click, 2).unwrap();
+
+        let mut samples = [0_u8; 16];
+        let mut len = 0;
+        while len < samples.len() {
+            let read = mixer.read(&mut samples[len..]).unwrap();
+            assert!(read != 0);
+            len += read;
+        }
+        assert!(mixer.is_eof());
+
+        let samples: [i16; 8] =
+            core::array::from_fn(|i| i16::from_le_bytes([samples[i * 2], samples[i * 2 + 1]]));
+        assert!(samples == [1, -2, 3, -3, 3, -3, 2, -1]);
+
+        mixer.restart().unwrap();
+        let mut sample = [0_u8; 2];
+        mixer.read(&mut sample).unwrap();
+        assert!(sample == [0x01, 0x00]);
+    }
+
+    #[test]
+    fn overlay_format_mismatch() {
+        let mixer = Mixer::new(
+            Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap(),
+            Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap(),
+        );
+        assert!(matches!(mixer, Err(Error::FormatMismatch)));
+    }
+}
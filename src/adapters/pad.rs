@@ -0,0 +1,133 @@
+use core::marker::PhantomData;
+
+use crate::{AudioFile, Channels, PlatformFile, SampleFormat};
+
+/// Plays `frames` frames of silence before a source
+pub struct Pad<File: PlatformFile, A: AudioFile<File>> {
+    source: A,
+    /// samples of silence before the source
+    silence: usize,
+    /// samples of silence played so far
+    silence_played: usize,
+    _file: PhantomData<File>,
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> Pad<File, A> {
+    /// restarts the source so playback begins with the silence
+    pub fn new(source: A, frames: usize) -> Result<Self, A::Error> {
+        let silence = frames * source.channels().count() as usize;
+        let mut pad = Self {
+            source,
+            silence,
+            silence_played: 0,
+            _file: PhantomData,
+        };
+        pad.restart()?;
+        Ok(pad)
+    }
+
+    /// get back the source
+    pub fn into_inner(self) -> A {
+        self.source
+    }
+}
+
+impl<File: PlatformFile, A: AudioFile<File>> AudioFile<File> for Pad<File, A> {
+    type Error = A::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if self.silence_played == self.silence {
+            return self.source.read(buf);
+        }
+
+        let format = self.sample_format();
+        let sample_size = format.size() as usize;
+        let samples = (buf.len() / sample_size).min(self.silence - self.silence_played);
+        for sample in buf[..samples * sample_size].chunks_exact_mut(sample_size) {
+            format.write_i32(0, sample);
+        }
+        self.silence_played += samples;
+        Ok(samples * sample_size)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.source.sample_rate()
+    }
+
+    fn channels(&self) -> Channels {
+        self.source.channels()
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.source.sample_format()
+    }
+
+    /// seeks before the start are clamped to the first frame of silence
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
+        let target = (self.played() as i64 + sample_offset).max(0) as usize;
+        if target < self.silence {
+            self.source.restart()?;
+            self.silence_played = target;
+            Ok(())
+        } else {
+            self.silence_played = self.silence;
+            let source_target = (target - self.silence) as i64;
+            self.source
+                .try_seek(source_target - self.source.played() as i64)
+        }
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.source.is_seekable()
+    }
+
+    fn played(&self) -> usize {
+        self.silence_played + self.source.played()
+    }
+
+    fn data_len(&self) -> usize {
+        self.silence * self.sample_format().size() as usize + self.source.data_len()
+    }
+
+    /// go back to the first frame of silence
+    fn restart(&mut self) -> Result<(), Self::Error> {
+        self.silence_played = 0;
+        self.source.restart()
+    }
+
+    fn is_eof(&self) -> bool {
+        self.silence_played == self.silence && self.source.is_eof()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pad;
+    use crate::{AudioFile, MONO_16BIT, TestFile, wav::Wav};
+
+    #[test]
+    fn leading_silence() {
+        let wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        let mut pad = Pad::new(wav, 2).unwrap();
+        assert!(pad.total_frames() == 6);
+
+        let mut samples = [0xaa_u8; 12];
+        assert!(pad.read(&mut samples) == Ok(4));
+        assert!(samples[..4] == [0x00; 4]);
+        assert!(pad.read(&mut samples[4..]) == Ok(8));
+        assert!(samples[4..] == MONO_16BIT[44..]);
+        assert!(pad.played() == 6);
+        assert!(pad.is_eof());
+
+        pad.restart().unwrap();
+        assert!(pad.played() == 0);
+        let mut sample = [0xaa_u8; 2];
+        pad.read(&mut sample).unwrap();
+        assert!(sample == [0x00, 0x00]);
+
+        // seek past the silence into the source
+        pad.try_seek(2).unwrap();
+        pad.read(&mut sample).unwrap();
+        assert!(sample == [0xfe, 0xff]);
+    }
+}
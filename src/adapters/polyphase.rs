@@ -0,0 +1,292 @@
+use core::f32::consts::PI;
+use core::marker::PhantomData;
+
+use crate::{AudioFile, Channels, MAX_CHANNELS, PlatformFile, SampleFormat};
+
+/// Largest integer factor a [`PolyphaseResampler`] upsamples by
+pub const MAX_UPSAMPLE_FACTOR: u16 = 8;
+
+/// Upsamples a source by an integer factor with a windowed sinc filter, such as 8 kHz or
+/// 16 kHz to 48 kHz
+///
+/// the `TAPS * factor` long filter is split into `factor` phases of `TAPS` taps, one for each
+/// output frame between two input frames, so every output sample only costs `TAPS`
+/// multiplies. More taps reject the images above the source rate better at the cost of a
+/// longer delay
+pub struct PolyphaseResampler<File: PlatformFile, A: AudioFile<File>, const TAPS: usize> {
+    source: A,
+    factor: u16,
+    /// Q15 coefficients of each phase, newest input sample first
+    phases: [[i32; TAPS]; MAX_UPSAMPLE_FACTOR as usize],
+    /// last `TAPS` input samples of each channel, a ring starting at `head`
+    delay: [[i32; TAPS]; MAX_CHANNELS as usize],
+    head: usize,
+    /// phase of the next output frame, a new input frame is read at phase 0
+    phase: u16,
+    played: usize,
+    _file: PhantomData<File>,
+}
+
+impl<File: PlatformFile, A: AudioFile<File>, const TAPS: usize> PolyphaseResampler<File, A, TAPS> {
+    /// panics if `factor` is 0 or above [`MAX_UPSAMPLE_FACTOR`], if `TAPS` is 0, or if the
+    /// output sample rate doesn't fit in 16 bits
+    pub fn new(source: A, factor: u16) -> Self {
+        assert!(
+            (1..=MAX_UPSAMPLE_FACTOR).contains(&factor),
+            "upsampling factor must be 1 to {MAX_UPSAMPLE_FACTOR}"
+        );
+        assert!(TAPS > 0, "the filter needs at least one tap");
+        assert!(
+            source.sample_rate().checked_mul(factor).is_some(),
+            "output sample rate must fit in 16 bits"
+        );
+
+        Self {
+            source,
+            factor,
+            phases: design_phases(factor as usize),
+            delay: [[0; TAPS]; MAX_CHANNELS as usize],
+            head: 0,
+            phase: 0,
+            played: 0,
+            _file: PhantomData,
+        }
+    }
+
+    /// get back the source
+    pub fn into_inner(self) -> A {
+        self.source
+    }
+
+    fn channel_count(&self) -> usize {
+        self.source.channels().count() as usize
+    }
+
+    fn reset(&mut self) {
+        self.delay = [[0; TAPS]; MAX_CHANNELS as usize];
+        self.head = 0;
+        self.phase = 0;
+    }
+
+    /// shift the next source frame into the delay lines, false once the source ended
+    fn push_frame(&mut self) -> Result<bool, A::Error> {
+        let format = self.source.sample_format();
+        let sample_size = format.size() as usize;
+        let channels = self.channel_count();
+
+        let mut frame = [0_u8; 64];
+        if !self.source.read_frame(&mut frame)? {
+            return Ok(false);
+        }
+        self.head = (self.head + TAPS - 1) % TAPS;
+        for (line, sample) in self.delay[..channels]
+            .iter_mut()
+            .zip(frame.chunks_exact(sample_size))
+        {
+            line[self.head] = format.to_i32(sample);
+        }
+        Ok(true)
+    }
+
+    /// filter the delay lines with the current phase into `out`
+    fn write_frame(&self, out: &mut [u8]) {
+        let format = self.source.sample_format();
+        let sample_size = format.size() as usize;
+        let coefficients = &self.phases[self.phase as usize];
+
+        for (line, sample) in self.delay.iter().zip(out.chunks_exact_mut(sample_size)) {
+            let mut sum = 0_i64;
+            for (k, coefficient) in coefficients.iter().enumerate() {
+                sum += *coefficient as i64 * line[(self.head + k) % TAPS] as i64;
+            }
+            let filtered = (sum >> 15).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+            format.write_i32(filtered, sample);
+        }
+    }
+}
+
+impl<File: PlatformFile, A: AudioFile<File>, const TAPS: usize> AudioFile<File>
+    for PolyphaseResampler<File, A, TAPS>
+{
+    type Error = A::Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let frame_size = self.frame_size();
+        let mut read = 0;
+        for out in buf.chunks_exact_mut(frame_size) {
+            if self.phase == 0 && !self.push_frame()? {
+                break;
+            }
+            self.write_frame(out);
+            self.phase = (self.phase + 1) % self.factor;
+            read += frame_size;
+        }
+        self.played += read / self.source.sample_format().size() as usize;
+        Ok(read)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.source.sample_rate() * self.factor
+    }
+
+    fn channels(&self) -> Channels {
+        self.source.channels()
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.source.sample_format()
+    }
+
+    /// lands on the start of a source frame, the filter starts over from silence
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
+        let channels = self.channel_count() as i64;
+        let factor = self.factor as i64;
+        let target = (self.played as i64 + sample_offset) / channels;
+        let target = target.max(0) / factor;
+        let current = self.source.played() as i64 / channels;
+        self.source.try_seek((target - current) * channels)?;
+        self.reset();
+        self.played = (target * factor * channels) as usize;
+        Ok(())
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.source.is_seekable()
+    }
+
+    fn played(&self) -> usize {
+        self.played
+    }
+
+    fn data_len(&self) -> usize {
+        self.source.total_frames() * self.factor as usize * self.frame_size()
+    }
+
+    fn restart(&mut self) -> Result<(), Self::Error> {
+        self.source.restart()?;
+        self.reset();
+        self.played = 0;
+        Ok(())
+    }
+
+    fn is_eof(&self) -> bool {
+        self.source.is_eof() && self.phase == 0
+    }
+}
+
+/// split a Hann windowed sinc low-pass at the source Nyquist rate into `factor` phases,
+/// each scaled to unity gain at DC
+fn design_phases<const TAPS: usize>(factor: usize) -> [[i32; TAPS]; MAX_UPSAMPLE_FACTOR as usize] {
+    let len = TAPS * factor;
+    let center = (len - 1) as f32 / 2.0;
+
+    let mut phases = [[0; TAPS]; MAX_UPSAMPLE_FACTOR as usize];
+    for (phase, coefficients) in phases[..factor].iter_mut().enumerate() {
+        let mut taps = [0.0_f32; TAPS];
+        for (k, tap) in taps.iter_mut().enumerate() {
+            let i = k * factor + phase;
+            let x = (i as f32 - center) / factor as f32;
+            let sinc = match x == 0.0 {
+                true => 1.0,
+                false => sin(PI * x) / (PI * x),
+            };
+            let window = match len {
+                1 => 1.0,
+                _ => 0.5 - 0.5 * sin(2.0 * PI * i as f32 / (len - 1) as f32 + PI / 2.0),
+            };
+            *tap = sinc * window;
+        }
+
+        let gain: f32 = taps.iter().sum();
+        for (coefficient, tap) in coefficients.iter_mut().zip(taps) {
+            let scaled = tap / gain * 32_768.0;
+            *coefficient = (scaled + 0.5_f32.copysign(scaled)) as i32;
+        }
+    }
+    phases
+}
+
+/// sine without std, folded into -pi/2..=pi/2 and approximated with a series, accurate to
+/// about 1e-7
+fn sin(x: f32) -> f32 {
+    // fold into -pi..pi, then mirror around +-pi/2
+    let turns = (x + PI) / (2.0 * PI);
+    let whole = turns as i32 - (turns < 0.0 && turns != turns as i32 as f32) as i32;
+    let x = x - 2.0 * PI * whole as f32;
+    let x = match x {
+        x if x > PI / 2.0 => PI - x,
+        x if x < -PI / 2.0 => -PI - x,
+        x => x,
+    };
+    let x2 = x * x;
+    x * (1.0
+        - x2 / 6.0 * (1.0 - x2 / 20.0 * (1.0 - x2 / 42.0 * (1.0 - x2 / 72.0 * (1.0 - x2 / 110.0)))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PolyphaseResampler, sin};
+    use crate::{AudioFile, SliceFile, wav::Wav};
+    use core::f32::consts::PI;
+
+    /// 8 kHz mono 16 bit wav of a 1 kHz sine
+    fn sine_wav(frames: usize) -> std::vec::Vec<u8> {
+        let mut bytes = std::vec::Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + frames as u32 * 2).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(&[
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+        ]);
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&(frames as u32 * 2).to_le_bytes());
+        for n in 0..frames {
+            let sample = (sin(2.0 * PI * n as f32 / 8.0) * 16_000.0) as i16;
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// power of `samples` at `frequency` Hz, sampled at 48 kHz
+    fn power(samples: &[i16], frequency: f32) -> f32 {
+        let (mut re, mut im) = (0.0, 0.0);
+        for (n, sample) in samples.iter().enumerate() {
+            let angle = 2.0 * PI * frequency * n as f32 / 48_000.0;
+            re += *sample as f32 * sin(angle + PI / 2.0);
+            im += *sample as f32 * sin(angle);
+        }
+        re * re + im * im
+    }
+
+    #[test]
+    fn upsample_sine() {
+        let bytes = sine_wav(96);
+        let wav = Wav::new(SliceFile::new(&bytes)).unwrap();
+        let mut resampler = PolyphaseResampler::<_, _, 16>::new(wav, 6);
+        assert!(resampler.sample_rate() == 48_000);
+        assert!(resampler.total_frames() == 96 * 6);
+
+        let mut samples = [0_i16; 96 * 6];
+        assert!(resampler.read_samples(&mut samples) == Ok(96 * 6));
+        assert!(resampler.is_eof());
+
+        // skip the filter delay, then whole periods only. Images are 40 dB down at least
+        let steady = &samples[96..96 + 48 * 8];
+        let signal = power(steady, 1_000.0);
+        for image in [7_000.0, 9_000.0, 15_000.0, 17_000.0] {
+            assert!(power(steady, image) * 10_000.0 < signal);
+        }
+
+        // the filter starts over from silence
+        resampler.restart().unwrap();
+        assert!(resampler.read_samples(&mut samples[..6]) == Ok(6));
+        assert!(samples[..6].iter().all(|sample| sample.abs() < 1_000));
+    }
+}
@@ -0,0 +1,234 @@
+//! Sample conversions used when decoding, for callers mixing or converting samples themselves
+//!
+//! Samples are widened to the full i32 range, so every format converts through one type
+
+use crate::{Q15, SampleFormat};
+
+/// Full scale of i32 samples when converting to and from float
+const I32_SCALE: f32 = 2_147_483_648.0;
+
+/// widen an unsigned 8 bit sample to the full i32 range, 128 is silence
+pub const fn u8_to_i32(sample: u8) -> i32 {
+    (sample as i32 - 128) << 24
+}
+
+/// widen a signed 8 bit sample to the full i32 range
+pub const fn i8_to_i32(sample: i8) -> i32 {
+    (sample as i32) << 24
+}
+
+/// widen a signed 16 bit sample to the full i32 range
+pub const fn i16_to_i32(sample: i16) -> i32 {
+    (sample as i32) << 16
+}
+
+/// sign extend a little endian 24 bit sample to the full i32 range
+pub const fn i24_to_i32(bytes: [u8; 3]) -> i32 {
+    i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]])
+}
+
+/// scale a float sample to the full i32 range, saturating values outside -1.0..=1.0
+pub fn f32_to_i32(sample: f32) -> i32 {
+    (sample * I32_SCALE) as i32
+}
+
+/// scale a double sample to the full i32 range, saturating values outside -1.0..=1.0
+pub fn f64_to_i32(sample: f64) -> i32 {
+    (sample * I32_SCALE as f64) as i32
+}
+
+/// narrow a full range i32 sample to unsigned 8 bit, dropping the low 24 bits
+pub const fn i32_to_u8(sample: i32) -> u8 {
+    ((sample >> 24) + 128) as u8
+}
+
+/// narrow a full range i32 sample to signed 8 bit, dropping the low 24 bits
+pub const fn i32_to_i8(sample: i32) -> i8 {
+    (sample >> 24) as i8
+}
+
+/// narrow a full range i32 sample, dropping the low 16 bits
+pub const fn i32_to_i16(sample: i32) -> i16 {
+    (sample >> 16) as i16
+}
+
+/// narrow a full range i32 sample to little endian 24 bit, dropping the low byte
+pub const fn i32_to_i24(sample: i32) -> [u8; 3] {
+    let bytes = sample.to_le_bytes();
+    [bytes[1], bytes[2], bytes[3]]
+}
+
+/// scale a full range i32 sample to a float in -1.0..1.0
+pub fn i32_to_f32(sample: i32) -> f32 {
+    sample as f32 / I32_SCALE
+}
+
+/// scale a full range i32 sample to a double in -1.0..1.0
+pub fn i32_to_f64(sample: i32) -> f64 {
+    sample as f64 / I32_SCALE as f64
+}
+
+/// widen an unsigned 8 bit sample, 128 is silence
+pub const fn u8_to_i16(sample: u8) -> i16 {
+    i32_to_i16(u8_to_i32(sample))
+}
+
+/// widen a signed 8 bit sample
+pub const fn i8_to_i16(sample: i8) -> i16 {
+    i32_to_i16(i8_to_i32(sample))
+}
+
+/// narrow a little endian 24 bit sample, dropping the low byte
+pub const fn i24_to_i16(bytes: [u8; 3]) -> i16 {
+    i32_to_i16(i24_to_i32(bytes))
+}
+
+/// scale a full range i32 sample by `gain`, saturating the one product that overflows
+/// (`i32::MIN` times -1.0)
+pub const fn scale(sample: i32, gain: Q15) -> i32 {
+    let scaled = (sample as i64 * gain.0 as i64) >> 15;
+    if scaled > i32::MAX as i64 {
+        i32::MAX
+    } else {
+        scaled as i32
+    }
+}
+
+/// add two samples, clipping to full scale instead of wrapping
+pub const fn saturating_mix_i16(a: i16, b: i16) -> i16 {
+    a.saturating_add(b)
+}
+
+const DITHER_SEED: u32 = 0x2545_f491;
+
+/// TPDF dither for narrowing full range i32 samples, triangular noise of +-1 lsb of the
+/// narrower format from a xorshift32 source
+///
+/// the sequence starts from the same seed every time, so output is repeatable
+pub struct Dither(u32);
+
+impl Dither {
+    pub const fn new() -> Self {
+        Self(DITHER_SEED)
+    }
+
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    /// add the noise for narrowing to `output` to a full range sample, saturating at full
+    /// scale
+    ///
+    /// 32 bit and float outputs already hold every bit of an i32 sample and are left as is
+    pub fn apply(&mut self, sample: i32, output: SampleFormat) -> i32 {
+        let shift = match output {
+            SampleFormat::I32 | SampleFormat::F32 | SampleFormat::F64 => return sample,
+            format => 8 * format.size() as u32,
+        };
+        let noise = (self.next() >> shift) as i32 - (self.next() >> shift) as i32;
+        sample.saturating_add(noise)
+    }
+}
+
+impl Default for Dither {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Dither, f32_to_i32, i8_to_i16, i8_to_i32, i24_to_i16, i24_to_i32, i32_to_f32, i32_to_i8,
+        i32_to_i16, i32_to_i24, i32_to_u8, saturating_mix_i16, scale, u8_to_i16, u8_to_i32,
+    };
+    use crate::{Q15, SampleFormat};
+
+    #[test]
+    fn widen_8bit() {
+        assert!(u8_to_i16(0) == i16::MIN);
+        assert!(u8_to_i16(128) == 0);
+        assert!(u8_to_i16(255) == 0x7f00);
+        assert!(i8_to_i16(i8::MIN) == i16::MIN);
+        assert!(i8_to_i16(0) == 0);
+        assert!(i8_to_i16(i8::MAX) == 0x7f00);
+    }
+
+    #[test]
+    fn round_trip_i32() {
+        for sample in [0_u8, 1, 127, 128, 255] {
+            assert!(i32_to_u8(u8_to_i32(sample)) == sample);
+        }
+        for sample in [i8::MIN, -1, 0, i8::MAX] {
+            assert!(i32_to_i8(i8_to_i32(sample)) == sample);
+        }
+        for bytes in [[0x56, 0x34, 0x12], [0x00, 0x00, 0x80], [0xff, 0xff, 0xff]] {
+            assert!(i32_to_i24(i24_to_i32(bytes)) == bytes);
+        }
+        assert!(f32_to_i32(1.0) == i32::MAX);
+        assert!(f32_to_i32(-1.0) == i32::MIN);
+        assert!(i32_to_f32(i32::MIN) == -1.0);
+        assert!(i32_to_f32(0x4000_0000) == 0.5);
+    }
+
+    #[test]
+    fn dither_noise() {
+        let mut dither = Dither::new();
+        // the noise is at most one 16 bit lsb either way
+        for _ in 0..64 {
+            let sample = dither.apply(0x1234_0000, SampleFormat::I16);
+            assert!((sample - 0x1234_0000).abs() < 0x1_0000);
+        }
+        // full scale saturates instead of wrapping around
+        for _ in 0..64 {
+            assert!(dither.apply(i32::MAX, SampleFormat::U8) > 0);
+        }
+
+        // the sequence repeats from a new dither
+        let mut a = Dither::new();
+        let mut b = Dither::default();
+        assert!(a.apply(0, SampleFormat::I24) == b.apply(0, SampleFormat::I24));
+
+        // nothing to add when the output holds the whole sample
+        assert!(dither.apply(123, SampleFormat::I32) == 123);
+        assert!(dither.apply(123, SampleFormat::F32) == 123);
+    }
+
+    #[test]
+    fn sign_extend_i24() {
+        assert!(i24_to_i32([0xff, 0xff, 0x7f]) == 0x7fff_ff00);
+        assert!(i24_to_i32([0x00, 0x00, 0x80]) == i32::MIN);
+        assert!(i24_to_i32([0xff, 0xff, 0xff]) == -256);
+        assert!(i24_to_i16([0xff, 0xff, 0x7f]) == i16::MAX);
+        assert!(i24_to_i16([0x00, 0x00, 0x80]) == i16::MIN);
+        // anything below one 16 bit lsb rounds towards negative infinity
+        assert!(i24_to_i16([0xff, 0xff, 0xff]) == -1);
+        assert!(i24_to_i16([0xff, 0x00, 0x00]) == 0);
+    }
+
+    #[test]
+    fn narrow_i32() {
+        assert!(i32_to_i16(i32::MAX) == i16::MAX);
+        assert!(i32_to_i16(i32::MIN) == i16::MIN);
+        assert!(i32_to_i16(0xffff) == 0);
+    }
+
+    #[test]
+    fn scale_by_gain() {
+        assert!(scale(i32::MIN, Q15::MIN) == i32::MAX);
+        assert!(scale(i32::MAX, Q15(0x4000)) == i32::MAX >> 1);
+        assert!(scale(i32::MIN, Q15(0x4000)) == i32::MIN >> 1);
+        assert!(scale(i32::MAX, Q15::MAX) < i32::MAX);
+        assert!(scale(1000, Q15(0)) == 0);
+    }
+
+    #[test]
+    fn mix_saturates() {
+        assert!(saturating_mix_i16(i16::MAX, 1) == i16::MAX);
+        assert!(saturating_mix_i16(i16::MIN, -1) == i16::MIN);
+        assert!(saturating_mix_i16(1000, -3000) == -2000);
+    }
+}
@@ -0,0 +1,136 @@
+use core::fmt::Debug;
+
+use crate::{AudioFile, Channels, PlatformFile, SampleFormat};
+
+/// Error of a [`DynAudioFile`], whichever error type the source returned
+pub type BoxedError = Box<dyn Debug + Send + Sync>;
+
+/// Object safe form of [`AudioFile`], so sources of different types reading the same kind of
+/// file can be held as a `Box<dyn DynAudioFile<File>>`
+///
+/// every [`AudioFile`] with a `Debug + Send + Sync` error implements it
+pub trait DynAudioFile<File: PlatformFile> {
+    /// see [`AudioFile::read`]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, BoxedError>;
+    /// see [`AudioFile::read_samples_i16`]
+    fn read_samples_i16(&mut self, buf: &mut [i16]) -> Result<usize, BoxedError>;
+    /// see [`AudioFile::sample_rate`]
+    fn sample_rate(&self) -> u16;
+    /// see [`AudioFile::channels`]
+    fn channels(&self) -> Channels;
+    /// see [`AudioFile::sample_format`]
+    fn sample_format(&self) -> SampleFormat;
+    /// see [`AudioFile::frame_size`]
+    fn frame_size(&self) -> usize;
+    /// see [`AudioFile::try_seek`]
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), BoxedError>;
+    /// see [`AudioFile::is_seekable`]
+    fn is_seekable(&self) -> bool;
+    /// see [`AudioFile::played`]
+    fn played(&self) -> usize;
+    /// see [`AudioFile::remaining`]
+    fn remaining(&self) -> usize;
+    /// see [`AudioFile::data_len`]
+    fn data_len(&self) -> usize;
+    /// see [`AudioFile::total_frames`]
+    fn total_frames(&self) -> usize;
+    /// see [`AudioFile::duration_ms`]
+    fn duration_ms(&self) -> u64;
+    /// see [`AudioFile::restart`]
+    fn restart(&mut self) -> Result<(), BoxedError>;
+    /// see [`AudioFile::is_eof`]
+    fn is_eof(&self) -> bool;
+}
+
+fn boxed<E: Debug + Send + Sync + 'static>(e: E) -> BoxedError {
+    Box::new(e)
+}
+
+impl<File, A> DynAudioFile<File> for A
+where
+    File: PlatformFile,
+    A: AudioFile<File>,
+    A::Error: Debug + Send + Sync + 'static,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, BoxedError> {
+        AudioFile::read(self, buf).map_err(boxed)
+    }
+
+    fn read_samples_i16(&mut self, buf: &mut [i16]) -> Result<usize, BoxedError> {
+        AudioFile::read_samples_i16(self, buf).map_err(boxed)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        AudioFile::sample_rate(self)
+    }
+
+    fn channels(&self) -> Channels {
+        AudioFile::channels(self)
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        AudioFile::sample_format(self)
+    }
+
+    fn frame_size(&self) -> usize {
+        AudioFile::frame_size(self)
+    }
+
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), BoxedError> {
+        AudioFile::try_seek(self, sample_offset).map_err(boxed)
+    }
+
+    fn is_seekable(&self) -> bool {
+        AudioFile::is_seekable(self)
+    }
+
+    fn played(&self) -> usize {
+        AudioFile::played(self)
+    }
+
+    fn remaining(&self) -> usize {
+        AudioFile::remaining(self)
+    }
+
+    fn data_len(&self) -> usize {
+        AudioFile::data_len(self)
+    }
+
+    fn total_frames(&self) -> usize {
+        AudioFile::total_frames(self)
+    }
+
+    fn duration_ms(&self) -> u64 {
+        AudioFile::duration_ms(self)
+    }
+
+    fn restart(&mut self) -> Result<(), BoxedError> {
+        AudioFile::restart(self).map_err(boxed)
+    }
+
+    fn is_eof(&self) -> bool {
+        AudioFile::is_eof(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DynAudioFile;
+    use crate::{Channels, MONO_16BIT, TestFile, wav::Wav};
+
+    #[test]
+    fn boxed_wav() {
+        let wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        let mut source: Box<dyn DynAudioFile<TestFile>> = Box::new(wav);
+        assert!(source.channels() == Channels::Mono);
+        assert!(source.total_frames() == 4);
+
+        let mut samples = [0_i16; 4];
+        assert!(source.read_samples_i16(&mut samples).unwrap() == 4);
+        assert!(samples == [1, -2, 2, -1]);
+        assert!(source.is_eof());
+
+        // errors come back boxed
+        assert!(source.try_seek(-100).is_err());
+    }
+}
@@ -8,34 +8,152 @@ use std::{
     io::{Read, Seek, SeekFrom},
 };
 
+pub mod adapters;
+pub mod convert;
+#[cfg(feature = "std")]
+mod dyn_audio;
 pub mod wav;
 
+#[cfg(feature = "std")]
+pub use dyn_audio::{BoxedError, DynAudioFile};
+
 /// File getters for accessing audio data across all supported containers/formats
 pub trait AudioFile<File: PlatformFile> {
     type Error;
 
     /// read audio samples from file
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+    /// read exactly one frame into the start of `buf`, returns `false` at EOF
+    ///
+    /// panics if `buf` is shorter than [`AudioFile::frame_size`]
+    fn read_frame(&mut self, buf: &mut [u8]) -> Result<bool, Self::Error> {
+        let frame = &mut buf[..self.frame_size()];
+        let mut read = 0;
+        while read < frame.len() {
+            let len = self.read(&mut frame[read..])?;
+            if len == 0 {
+                return Ok(false);
+            }
+            read += len;
+        }
+        Ok(true)
+    }
+    /// read the frames lasting `ms` milliseconds into the start of `buf`, returns the number of
+    /// frames read which is only less at EOF
+    ///
+    /// the frame count `ms * sample_rate / 1000` is rounded down, so durations that aren't a
+    /// whole number of frames come up short. Panics if `buf` can't hold the frames
+    fn read_ms(&mut self, ms: u32, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let frames = (ms as u64 * self.sample_rate() as u64 / 1000) as usize;
+        let frame_size = self.frame_size();
+        let out = &mut buf[..frames * frame_size];
+        let mut read = 0;
+        while read < out.len() {
+            let len = self.read(&mut out[read..])?;
+            if len == 0 {
+                break;
+            }
+            read += len;
+        }
+        Ok(read / frame_size)
+    }
+    /// read audio samples converted to `S`, returns the number of samples read
+    fn read_samples<S: Sample>(&mut self, buf: &mut [S]) -> Result<usize, Self::Error> {
+        let format = self.sample_format();
+        let sample_size = format.size() as usize;
+
+        let mut scratch = [0_u8; 64];
+        let chunk_len = scratch.len() - scratch.len() % self.frame_size();
+        let mut read = 0;
+        while read < buf.len() {
+            let want = ((buf.len() - read) * sample_size).min(chunk_len);
+            let len = self.read(&mut scratch[..want])?;
+            if len == 0 {
+                break;
+            }
+            for (out, bytes) in buf[read..]
+                .iter_mut()
+                .zip(scratch[..len].chunks_exact(sample_size))
+            {
+                *out = S::from_format(bytes, format, Endian::Little);
+            }
+            read += len / sample_size;
+        }
+        Ok(read)
+    }
+    /// read audio samples converted to i16, returns the number of samples read
+    fn read_samples_i16(&mut self, buf: &mut [i16]) -> Result<usize, Self::Error> {
+        self.read_samples(buf)
+    }
+    /// read audio samples as Q1.15 fixed point, returns the number of samples read
+    fn read_samples_q15(&mut self, buf: &mut [Q15]) -> Result<usize, Self::Error> {
+        self.read_samples(buf)
+    }
     /// get the sample rate
     fn sample_rate(&self) -> u16;
     /// get the number of channels
     fn channels(&self) -> Channels;
     /// get the format of the audio samples
     fn sample_format(&self) -> SampleFormat;
+    /// get the number of bytes in one frame (one sample for every channel)
+    fn frame_size(&self) -> usize {
+        frame_size(self.sample_format(), self.channels())
+    }
+    /// get the number of bytes played per second, for sizing buffers in units of time
+    fn byte_rate(&self) -> u32 {
+        self.sample_rate() as u32 * self.frame_size() as u32
+    }
+    /// get the bits played per second, compressed formats should derive it from the data
+    /// length and duration instead
+    fn bitrate_bps(&self) -> u32 {
+        self.byte_rate() * 8
+    }
     /// try to seek (from current sample) to audio sample offset NOT file byte offset
     fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error>;
+    /// check if the source can seek, otherwise [`AudioFile::try_seek`] and
+    /// [`AudioFile::restart`] fail
+    fn is_seekable(&self) -> bool {
+        true
+    }
     /// get how many samples have been read
     fn played(&self) -> usize;
+    /// get how many samples are left to read, 0 if that's unknown such as on a live stream
+    fn remaining(&self) -> usize {
+        0
+    }
+    /// get the total length of the audio data in bytes
+    ///
+    /// defaults to the samples played and remaining, sources that know their length up front
+    /// should return it directly
+    fn data_len(&self) -> usize {
+        (self.played() + self.remaining()) * self.sample_format().size() as usize
+    }
+    /// get the total number of frames in the audio data
+    fn total_frames(&self) -> usize {
+        self.data_len() / self.frame_size()
+    }
+    /// get the length of the audio in milliseconds, 0 if the sample rate is 0
+    fn duration_ms(&self) -> u64 {
+        (self.total_frames() as u64 * 1000)
+            .checked_div(self.sample_rate() as u64)
+            .unwrap_or(0)
+    }
     /// start back from the first sample
     fn restart(&mut self) -> Result<(), Self::Error> {
         self.try_seek(-(self.played() as i64))
     }
+    /// skip `frames` frames without reading them, stopping at EOF
+    fn skip_frames(&mut self, frames: usize) -> Result<(), Self::Error> {
+        let channels = self.channels().count() as usize;
+        let left = self.total_frames().saturating_sub(self.played() / channels);
+        self.try_seek((frames.min(left) * channels) as i64)
+    }
     /// check if EOF
     fn is_eof(&self) -> bool;
 }
 
 /// Data type of audio sample encoding
-#[derive(PartialEq, Eq, Copy, Clone)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum SampleFormat {
     /// Signed 8 bit audio
     I8,
@@ -45,54 +163,255 @@ pub enum SampleFormat {
     I16,
     /// Singed 24 bit audio
     I24,
+    /// Signed 32 bit audio
+    I32,
+    /// 32 bit IEEE float audio in the range -1.0..=1.0
+    F32,
+    /// 64 bit IEEE float audio in the range -1.0..=1.0, narrowed when converted
+    F64,
 }
 
 impl SampleFormat {
     /// number of bytes the sample format consumes
-    fn size(&self) -> u8 {
+    ///
+    /// ```
+    /// use embedded_audio::SampleFormat;
+    ///
+    /// assert_eq!(SampleFormat::I16.size(), 2);
+    /// ```
+    pub const fn size(&self) -> u8 {
         match self {
             SampleFormat::I8 => 1,
             SampleFormat::U8 => 1,
             SampleFormat::I16 => 2,
             SampleFormat::I24 => 3,
+            SampleFormat::I32 => 4,
+            SampleFormat::F32 => 4,
+            SampleFormat::F64 => 8,
+        }
+    }
+
+    /// convert one little endian sample to a signed 16 bit sample
+    fn to_i16(self, bytes: &[u8]) -> i16 {
+        convert::i32_to_i16(self.to_i32(bytes))
+    }
+
+    /// decode one little endian sample scaled to the full i32 range
+    fn to_i32(self, bytes: &[u8]) -> i32 {
+        match self {
+            SampleFormat::I8 => convert::i8_to_i32(bytes[0] as i8),
+            SampleFormat::U8 => convert::u8_to_i32(bytes[0]),
+            SampleFormat::I16 => convert::i16_to_i32(i16::from_le_bytes([bytes[0], bytes[1]])),
+            SampleFormat::I24 => convert::i24_to_i32([bytes[0], bytes[1], bytes[2]]),
+            SampleFormat::I32 => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            SampleFormat::F32 => {
+                convert::f32_to_i32(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+            SampleFormat::F64 => {
+                convert::f64_to_i32(f64::from_le_bytes(bytes[..8].try_into().unwrap()))
+            }
+        }
+    }
+
+    /// encode a full i32 range sample as one little endian sample, truncating extra precision
+    fn write_i32(self, sample: i32, out: &mut [u8]) {
+        match self {
+            SampleFormat::I8 => out[0] = convert::i32_to_i8(sample) as u8,
+            SampleFormat::U8 => out[0] = convert::i32_to_u8(sample),
+            SampleFormat::I16 => {
+                out[..2].copy_from_slice(&convert::i32_to_i16(sample).to_le_bytes())
+            }
+            SampleFormat::I24 => out[..3].copy_from_slice(&convert::i32_to_i24(sample)),
+            SampleFormat::I32 => out[..4].copy_from_slice(&sample.to_le_bytes()),
+            SampleFormat::F32 => {
+                out[..4].copy_from_slice(&convert::i32_to_f32(sample).to_le_bytes())
+            }
+            SampleFormat::F64 => {
+                out[..8].copy_from_slice(&convert::i32_to_f64(sample).to_le_bytes())
+            }
         }
     }
 }
 
+/// Q1.15 fixed point sample, full scale is `-1.0..1.0`
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug, Default)]
+pub struct Q15(pub i16);
+
+impl Q15 {
+    /// just under 1.0
+    pub const MAX: Self = Self(i16::MAX);
+    /// -1.0
+    pub const MIN: Self = Self(i16::MIN);
+
+    /// convert to a float in the range `-1.0..1.0`
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / 32_768.0
+    }
+}
+
+/// A type samples can be decoded into, see [`AudioFile::read_samples`]
+pub trait Sample: Copy {
+    /// decode one sample stored as `format` in `endian` byte order
+    fn from_format(bytes: &[u8], format: SampleFormat, endian: Endian) -> Self;
+}
+
+/// copy a sample into little endian order, padded to the widest sample
+fn little_endian(bytes: &[u8], endian: Endian) -> [u8; 8] {
+    let mut out = [0_u8; 8];
+    out[..bytes.len()].copy_from_slice(bytes);
+    if endian == Endian::Big {
+        out[..bytes.len()].reverse();
+    }
+    out
+}
+
+impl Sample for i8 {
+    fn from_format(bytes: &[u8], format: SampleFormat, endian: Endian) -> Self {
+        convert::i32_to_i8(format.to_i32(&little_endian(bytes, endian)))
+    }
+}
+
+impl Sample for u8 {
+    fn from_format(bytes: &[u8], format: SampleFormat, endian: Endian) -> Self {
+        convert::i32_to_u8(format.to_i32(&little_endian(bytes, endian)))
+    }
+}
+
+impl Sample for i16 {
+    fn from_format(bytes: &[u8], format: SampleFormat, endian: Endian) -> Self {
+        format.to_i16(&little_endian(bytes, endian))
+    }
+}
+
+impl Sample for i32 {
+    fn from_format(bytes: &[u8], format: SampleFormat, endian: Endian) -> Self {
+        format.to_i32(&little_endian(bytes, endian))
+    }
+}
+
+impl Sample for f32 {
+    fn from_format(bytes: &[u8], format: SampleFormat, endian: Endian) -> Self {
+        let bytes = little_endian(bytes, endian);
+        match format {
+            // skip the round trip through i32 so values outside -1.0..=1.0 are kept
+            SampleFormat::F32 => f32::from_le_bytes(bytes[..4].try_into().unwrap()),
+            SampleFormat::F64 => f64::from_le_bytes(bytes) as f32,
+            _ => convert::i32_to_f32(format.to_i32(&bytes)),
+        }
+    }
+}
+
+impl Sample for Q15 {
+    fn from_format(bytes: &[u8], format: SampleFormat, endian: Endian) -> Self {
+        Self(i16::from_format(bytes, format, endian))
+    }
+}
+
+/// Maximum number of channels supported in a single file
+pub const MAX_CHANNELS: u16 = 8;
+
 /// Number and type (interleaved or not) of audio channels
-#[derive(PartialEq, Eq, Copy, Clone)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum Channels {
     Mono,
     Stereo,
+    /// Interleaved audio with more than two channels
+    Multi(u16),
 }
 
-impl From<Channels> for u16 {
-    fn from(val: Channels) -> Self {
-        match val {
+impl Channels {
+    /// Front left, front right, back left, back right
+    pub const QUAD: Channels = Channels::Multi(4);
+    /// Front left, front right, center, LFE, back left, back right
+    pub const SURROUND_5_1: Channels = Channels::Multi(6);
+    /// 5.1 plus side left and side right
+    pub const SURROUND_7_1: Channels = Channels::Multi(8);
+
+    /// get the channel layout for a raw channel count
+    pub fn from_count(count: u16) -> Result<Self, Error> {
+        match count {
+            1 => Ok(Channels::Mono),
+            2 => Ok(Channels::Stereo),
+            3..=MAX_CHANNELS => Ok(Channels::Multi(count)),
+            _ => Err(Error::UnsupportedChannelCount),
+        }
+    }
+
+    /// number of channels in each frame
+    pub const fn count(&self) -> u16 {
+        match self {
             Channels::Mono => 1,
             Channels::Stereo => 2,
+            Channels::Multi(count) => *count,
         }
     }
 }
 
+impl From<Channels> for u16 {
+    fn from(val: Channels) -> Self {
+        val.count()
+    }
+}
+
+/// number of bytes in one frame (one sample for every channel)
+///
+/// usable in const contexts to size buffers when the format is known ahead of time
+///
+/// ```
+/// use embedded_audio::{Channels, SampleFormat, frame_size};
+///
+/// let dma_buf = [0_u8; frame_size(SampleFormat::I16, Channels::Stereo) * 256];
+/// assert_eq!(dma_buf.len(), 1024);
+/// ```
+pub const fn frame_size(format: SampleFormat, channels: Channels) -> usize {
+    format.size() as usize * channels.count() as usize
+}
+
+/// Byte order of multi byte samples
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
 // /// Types of interleaving stereo audio
 // pub enum Interleave {
 
 // }
 
+/// Errors shared by all containers/formats
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Unsupported channel count
+    UnsupportedChannelCount,
+    /// Sources do not share the same sample rate, channels and sample format
+    FormatMismatch,
+    /// The source can't seek back to its first sample
+    SeekUnsupported,
+    /// The source doesn't know how many frames it holds
+    UnknownLength,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum PlatformFileError {
     SeekOutofBounds,
     EOF,
+    /// The backend failed to read, such as an SD card error that may not happen again
+    Io,
 }
 
 /// Platform agnostic file for accessing audio data
 pub trait PlatformFile {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, PlatformFileError>;
     fn seek_from_current(&mut self, offset: i64) -> Result<(), PlatformFileError>;
-    fn seek_from_start(&mut self, offset: usize) -> Result<(), PlatformFileError>;
-    fn seek_from_end(&mut self, offset: usize) -> Result<(), PlatformFileError>;
-    fn length(&mut self) -> usize;
+    fn seek_from_start(&mut self, offset: u64) -> Result<(), PlatformFileError>;
+    fn seek_from_end(&mut self, offset: u64) -> Result<(), PlatformFileError>;
+    /// length in bytes, 64 bit so files past 4 GiB fit on 32 bit targets
+    fn length(&mut self) -> u64;
+    /// check if the backend supports seeking, such as a file compared to a network stream
+    fn can_seek(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(feature = "embedded-sdmmc")]
@@ -105,30 +424,37 @@ impl<
 > PlatformFile for File<'_, D, T, MAX_DIRS, MAX_FILES, MAX_VOLUMES>
 {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, PlatformFileError> {
-        File::read(self, buf).map_err(|_| PlatformFileError::EOF)
+        File::read(self, buf).map_err(|e| match e {
+            embedded_sdmmc::Error::EndOfFile => PlatformFileError::EOF,
+            _ => PlatformFileError::Io,
+        })
     }
 
     fn seek_from_current(&mut self, offset: i64) -> Result<(), PlatformFileError> {
-        File::seek_from_current(self, offset as i32).map_err(|_| PlatformFileError::SeekOutofBounds)
+        let offset = i32::try_from(offset).map_err(|_| PlatformFileError::SeekOutofBounds)?;
+        File::seek_from_current(self, offset).map_err(|_| PlatformFileError::SeekOutofBounds)
     }
 
-    fn seek_from_start(&mut self, offset: usize) -> Result<(), PlatformFileError> {
-        File::seek_from_start(self, offset as u32).map_err(|_| PlatformFileError::SeekOutofBounds)
+    fn seek_from_start(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+        let offset = u32::try_from(offset).map_err(|_| PlatformFileError::SeekOutofBounds)?;
+        File::seek_from_start(self, offset).map_err(|_| PlatformFileError::SeekOutofBounds)
     }
 
-    fn seek_from_end(&mut self, offset: usize) -> Result<(), PlatformFileError> {
-        File::seek_from_end(self, offset as u32).map_err(|_| PlatformFileError::SeekOutofBounds)
+    fn seek_from_end(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+        let offset = u32::try_from(offset).map_err(|_| PlatformFileError::SeekOutofBounds)?;
+        File::seek_from_end(self, offset).map_err(|_| PlatformFileError::SeekOutofBounds)
     }
 
-    fn length(&mut self) -> usize {
-        File::length(&self) as usize
+    fn length(&mut self) -> u64 {
+        File::length(self) as u64
     }
 }
 
 #[cfg(feature = "std")]
 impl PlatformFile for File {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, PlatformFileError> {
-        Read::read(self, buf).map_err(|_| PlatformFileError::EOF)
+        // std reports the end of the file as a read of 0 bytes, so any error is from the backend
+        Read::read(self, buf).map_err(|_| PlatformFileError::Io)
     }
 
     fn seek_from_current(&mut self, offset: i64) -> Result<(), PlatformFileError> {
@@ -138,25 +464,185 @@ impl PlatformFile for File {
         }
     }
 
-    fn seek_from_start(&mut self, offset: usize) -> Result<(), PlatformFileError> {
-        match Seek::seek(self, SeekFrom::Start(offset as u64)) {
+    fn seek_from_start(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+        match Seek::seek(self, SeekFrom::Start(offset)) {
             Ok(_) => Ok(()),
             Err(_) => Err(PlatformFileError::SeekOutofBounds),
         }
     }
 
-    fn seek_from_end(&mut self, offset: usize) -> Result<(), PlatformFileError> {
+    fn seek_from_end(&mut self, offset: u64) -> Result<(), PlatformFileError> {
         match Seek::seek(self, SeekFrom::End(offset as i64)) {
             Ok(_) => Ok(()),
             Err(_) => Err(PlatformFileError::SeekOutofBounds),
         }
     }
 
-    fn length(&mut self) -> usize {
-        File::metadata(&self).unwrap().len() as usize
+    fn length(&mut self) -> u64 {
+        File::metadata(self).unwrap().len()
     }
 }
 
+/// File over bytes already in memory, such as a whole file read up front, so parsing and
+/// reads are plain copies out of the slice
+pub struct SliceFile<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SliceFile<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// get back the bytes
+    pub fn into_inner(self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+impl PlatformFile for SliceFile<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, PlatformFileError> {
+        let left = &self.bytes[self.pos..];
+        if left.is_empty() {
+            return Err(PlatformFileError::EOF);
+        }
+        let len = buf.len().min(left.len());
+        buf[..len].copy_from_slice(&left[..len]);
+        self.pos += len;
+        Ok(len)
+    }
+
+    fn seek_from_current(&mut self, offset: i64) -> Result<(), PlatformFileError> {
+        let pos = (self.pos as i64)
+            .checked_add(offset)
+            .ok_or(PlatformFileError::SeekOutofBounds)?;
+        self.seek_from_start(u64::try_from(pos).map_err(|_| PlatformFileError::SeekOutofBounds)?)
+    }
+
+    fn seek_from_start(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+        if offset > self.bytes.len() as u64 {
+            return Err(PlatformFileError::SeekOutofBounds);
+        }
+        self.pos = offset as usize;
+        Ok(())
+    }
+
+    fn seek_from_end(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+        let offset = usize::try_from(offset).map_err(|_| PlatformFileError::SeekOutofBounds)?;
+        self.pos = self
+            .bytes
+            .len()
+            .checked_sub(offset)
+            .ok_or(PlatformFileError::SeekOutofBounds)?;
+        Ok(())
+    }
+
+    fn length(&mut self) -> u64 {
+        self.bytes.len() as u64
+    }
+}
+
+/// File that starts `base` bytes into another one, such as a WAV after some stray bytes
+pub struct OffsetFile<File: PlatformFile> {
+    file: File,
+    base: u64,
+}
+
+impl<File: PlatformFile> OffsetFile<File> {
+    /// moves `file` to `base`, the new start
+    pub fn new(mut file: File, base: u64) -> Result<Self, PlatformFileError> {
+        file.seek_from_start(base)?;
+        Ok(Self { file, base })
+    }
+
+    /// get the offset of the start within the underlying file
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// get back the underlying file
+    pub fn into_inner(self) -> File {
+        self.file
+    }
+}
+
+impl<File: PlatformFile> PlatformFile for OffsetFile<File> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, PlatformFileError> {
+        self.file.read(buf)
+    }
+
+    fn seek_from_current(&mut self, offset: i64) -> Result<(), PlatformFileError> {
+        self.file.seek_from_current(offset)
+    }
+
+    fn seek_from_start(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+        let offset = offset
+            .checked_add(self.base)
+            .ok_or(PlatformFileError::SeekOutofBounds)?;
+        self.file.seek_from_start(offset)
+    }
+
+    fn seek_from_end(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+        if offset > self.length() {
+            return Err(PlatformFileError::SeekOutofBounds);
+        }
+        self.file.seek_from_end(offset)
+    }
+
+    fn length(&mut self) -> u64 {
+        self.file.length().saturating_sub(self.base)
+    }
+
+    fn can_seek(&self) -> bool {
+        self.file.can_seek()
+    }
+}
+
+#[cfg(test)]
+/// 16 bit 8k mono with four samples
+const MONO_16BIT: &[u8] = &[
+    0x52, 0x49, 0x46, 0x46, // RIFF
+    0x32, 0x00, 0x00, 0x00, // chunk size
+    0x57, 0x41, 0x56, 0x45, // WAVE
+    0x66, 0x6d, 0x74, 0x20, // fmt
+    0x10, 0x00, 0x00, 0x00, // fmt chunk size
+    0x01, 0x00, // audio format
+    0x01, 0x00, // channel count
+    0x40, 0x1f, 0x00, 0x00, // sample rate
+    0x80, 0x3e, 0x00, 0x00, // byte rate
+    0x20, 0x00, // block align
+    0x10, 0x00, // bits per sample
+    0x64, 0x61, 0x74, 0x61, // data
+    0x08, 0x00, 0x00, 0x00, // data chunk size
+    0x01, 0x00, // sample 1
+    0xfe, 0xff, // sample 2
+    0x02, 0x00, // sample 3
+    0xff, 0xff, // sample 4
+];
+
+#[cfg(test)]
+/// 8 bit 8k stereo with four frames
+const STEREO_8BIT: &[u8] = &[
+    0x52, 0x49, 0x46, 0x46, // RIFF
+    0x32, 0x00, 0x00, 0x00, // chunk size
+    0x57, 0x41, 0x56, 0x45, // WAVE
+    0x66, 0x6d, 0x74, 0x20, // fmt
+    0x10, 0x00, 0x00, 0x00, // fmt chunk size
+    0x01, 0x00, // audio format
+    0x02, 0x00, // channel count
+    0x40, 0x1f, 0x00, 0x00, // sample rate
+    0x80, 0x3e, 0x00, 0x00, // byte rate
+    0x20, 0x00, // block align
+    0x08, 0x00, // bits per sample
+    0x64, 0x61, 0x74, 0x61, // data
+    0x08, 0x00, 0x00, 0x00, // data chunk size
+    0x01, 0x00, // sample 1 L+R
+    0xfe, 0xff, // sample 2 L+R
+    0x02, 0x00, // sample 3 L+R
+    0xff, 0xff, // sample 4 L+R
+];
+
 #[cfg(test)]
 /// Simple wrapper to test file decodes in tests
 struct TestFile {
@@ -197,31 +683,376 @@ impl PlatformFile for TestFile {
     }
 
     fn seek_from_current(&mut self, offset: i64) -> Result<(), PlatformFileError> {
-        if offset + self.current_pos as i64 > self.contents.len() as i64 {
+        let pos = self.current_pos as i64 + offset;
+        if pos < 0 || pos > self.contents.len() as i64 {
             return Err(PlatformFileError::SeekOutofBounds);
         }
-        if offset.is_positive() {
-            self.current_pos += offset as u16;
-        } else {
-            self.current_pos -= offset as u16;
-        }
+        self.current_pos = pos as u16;
         Ok(())
     }
 
-    fn seek_from_start(&mut self, offset: usize) -> Result<(), PlatformFileError> {
-        if offset > self.contents.len() {
+    fn seek_from_start(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+        if offset > self.contents.len() as u64 {
             return Err(PlatformFileError::SeekOutofBounds);
         }
         self.current_pos = offset as u16;
         Ok(())
     }
 
-    fn seek_from_end(&mut self, offset: usize) -> Result<(), PlatformFileError> {
-        self.current_pos = (self.contents.len() - offset) as u16;
+    fn seek_from_end(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+        self.current_pos = (self.contents.len() as u64 - offset) as u16;
         Ok(())
     }
 
-    fn length(&mut self) -> usize {
-        self.contents.len()
+    fn length(&mut self) -> u64 {
+        self.contents.len() as u64
+    }
+}
+
+/// [`PlatformFile`] fakes for backends that misbehave, wrapping a [`TestFile`]
+#[cfg(test)]
+mod test_files {
+    use crate::{PlatformFile, PlatformFileError, TestFile};
+
+    /// Returns at most one byte per read like a slow block device
+    pub(crate) struct ShortReadFile(pub(crate) TestFile);
+
+    impl PlatformFile for ShortReadFile {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, PlatformFileError> {
+            let len = buf.len().min(1);
+            self.0.read(&mut buf[..len])
+        }
+
+        fn seek_from_current(&mut self, offset: i64) -> Result<(), PlatformFileError> {
+            self.0.seek_from_current(offset)
+        }
+
+        fn seek_from_start(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+            self.0.seek_from_start(offset)
+        }
+
+        fn seek_from_end(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+            self.0.seek_from_end(offset)
+        }
+
+        fn length(&mut self) -> u64 {
+            self.0.length()
+        }
+    }
+
+    /// Pipe like file that can only be read front to back
+    pub(crate) struct StreamFile(pub(crate) TestFile);
+
+    impl PlatformFile for StreamFile {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, PlatformFileError> {
+            self.0.read(buf)
+        }
+
+        fn seek_from_current(&mut self, _offset: i64) -> Result<(), PlatformFileError> {
+            Err(PlatformFileError::SeekOutofBounds)
+        }
+
+        fn seek_from_start(&mut self, _offset: u64) -> Result<(), PlatformFileError> {
+            Err(PlatformFileError::SeekOutofBounds)
+        }
+
+        fn seek_from_end(&mut self, _offset: u64) -> Result<(), PlatformFileError> {
+            Err(PlatformFileError::SeekOutofBounds)
+        }
+
+        fn length(&mut self) -> u64 {
+            self.0.length()
+        }
+
+        fn can_seek(&self) -> bool {
+            false
+        }
+    }
+
+    /// A sparse file larger than 4 GiB, zeros except for `regions`
+    pub(crate) struct LargeFile {
+        pub(crate) regions: &'static [(u64, &'static [u8])],
+        pub(crate) len: u64,
+        pub(crate) pos: u64,
+    }
+
+    impl PlatformFile for LargeFile {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, PlatformFileError> {
+            let len = (buf.len() as u64).min(self.len - self.pos) as usize;
+            buf[..len].fill(0);
+            for (start, bytes) in self.regions {
+                for (i, byte) in bytes.iter().enumerate() {
+                    let offset = start + i as u64;
+                    if offset >= self.pos && offset < self.pos + len as u64 {
+                        buf[(offset - self.pos) as usize] = *byte;
+                    }
+                }
+            }
+            self.pos += len as u64;
+            Ok(len)
+        }
+
+        fn seek_from_current(&mut self, offset: i64) -> Result<(), PlatformFileError> {
+            self.seek_from_start(self.pos.wrapping_add_signed(offset))
+        }
+
+        fn seek_from_start(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+            if offset > self.len {
+                return Err(PlatformFileError::SeekOutofBounds);
+            }
+            self.pos = offset;
+            Ok(())
+        }
+
+        fn seek_from_end(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+            self.seek_from_start(self.len - offset)
+        }
+
+        fn length(&mut self) -> u64 {
+            self.len
+        }
+    }
+
+    /// Once `flaky` is set fails every other read, so each read only gets part of the buffer
+    #[cfg(feature = "diagnostics")]
+    pub(crate) struct FlakyFile {
+        pub(crate) file: TestFile,
+        pub(crate) flaky: bool,
+        pub(crate) fail: bool,
+    }
+
+    #[cfg(feature = "diagnostics")]
+    impl PlatformFile for FlakyFile {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, PlatformFileError> {
+            if !self.flaky {
+                return self.file.read(buf);
+            }
+            self.fail = !self.fail;
+            let len = buf.len().min(1);
+            match self.fail {
+                true => self.file.read(&mut buf[..len]),
+                false => Err(PlatformFileError::SeekOutofBounds),
+            }
+        }
+
+        fn seek_from_current(&mut self, offset: i64) -> Result<(), PlatformFileError> {
+            self.file.seek_from_current(offset)
+        }
+
+        fn seek_from_start(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+            self.file.seek_from_start(offset)
+        }
+
+        fn seek_from_end(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+            self.file.seek_from_end(offset)
+        }
+
+        fn length(&mut self) -> u64 {
+            self.file.length()
+        }
+    }
+
+    /// The next `torn` reads return one byte and the read after each fails with
+    /// [`PlatformFileError::Io`]
+    pub(crate) struct TornReadFile {
+        pub(crate) file: TestFile,
+        pub(crate) torn: u8,
+        pub(crate) fail: bool,
+    }
+
+    impl PlatformFile for TornReadFile {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, PlatformFileError> {
+            if self.fail {
+                self.fail = false;
+                // a failed read can leave the file anywhere
+                self.file.seek_from_end(0)?;
+                return Err(PlatformFileError::Io);
+            }
+            if self.torn > 0 {
+                self.torn -= 1;
+                self.fail = true;
+                let len = buf.len().min(1);
+                return self.file.read(&mut buf[..len]);
+            }
+            self.file.read(buf)
+        }
+
+        fn seek_from_current(&mut self, offset: i64) -> Result<(), PlatformFileError> {
+            self.file.seek_from_current(offset)
+        }
+
+        fn seek_from_start(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+            self.file.seek_from_start(offset)
+        }
+
+        fn seek_from_end(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+            self.file.seek_from_end(offset)
+        }
+
+        fn length(&mut self) -> u64 {
+            self.file.length()
+        }
+    }
+
+    /// Fails the next `failures` reads with [`PlatformFileError::Io`]
+    pub(crate) struct FailingFile {
+        pub(crate) file: TestFile,
+        pub(crate) failures: u8,
+    }
+
+    impl PlatformFile for FailingFile {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, PlatformFileError> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                // a failed read can leave the file anywhere
+                self.file.seek_from_end(0)?;
+                return Err(PlatformFileError::Io);
+            }
+            self.file.read(buf)
+        }
+
+        fn seek_from_current(&mut self, offset: i64) -> Result<(), PlatformFileError> {
+            self.file.seek_from_current(offset)
+        }
+
+        fn seek_from_start(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+            self.file.seek_from_start(offset)
+        }
+
+        fn seek_from_end(&mut self, offset: u64) -> Result<(), PlatformFileError> {
+            self.file.seek_from_end(offset)
+        }
+
+        fn length(&mut self) -> u64 {
+            self.file.length()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        AudioFile, Channels, Endian, Error, MONO_16BIT, PlatformFile, Sample, SampleFormat,
+        SliceFile, TestFile, frame_size, wav::Wav,
+    };
+
+    /// counts down silent 16 bit mono samples, only implementing the required methods
+    struct Countdown {
+        played: usize,
+        left: usize,
+    }
+
+    impl AudioFile<TestFile> for Countdown {
+        type Error = ();
+
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+            let samples = (buf.len() / 2).min(self.left);
+            buf[..samples * 2].fill(0);
+            self.played += samples;
+            self.left -= samples;
+            Ok(samples * 2)
+        }
+
+        fn sample_rate(&self) -> u16 {
+            8_000
+        }
+
+        fn channels(&self) -> Channels {
+            Channels::Mono
+        }
+
+        fn sample_format(&self) -> SampleFormat {
+            SampleFormat::I16
+        }
+
+        fn try_seek(&mut self, _sample_offset: i64) -> Result<(), ()> {
+            Err(())
+        }
+
+        fn played(&self) -> usize {
+            self.played
+        }
+
+        fn remaining(&self) -> usize {
+            self.left
+        }
+
+        fn is_eof(&self) -> bool {
+            self.left == 0
+        }
+    }
+
+    #[test]
+    fn default_data_len() {
+        let mut source = Countdown { played: 0, left: 6 };
+        assert!(source.data_len() == 12);
+        assert!(source.total_frames() == 6);
+
+        let mut buf = [0xaa_u8; 4];
+        assert!(source.read(&mut buf) == Ok(4));
+        assert!(source.data_len() == 12);
+    }
+
+    #[test]
+    fn channels_from_count() {
+        assert!(Channels::from_count(1) == Ok(Channels::Mono));
+        assert!(Channels::from_count(2) == Ok(Channels::Stereo));
+        assert!(Channels::from_count(4) == Ok(Channels::QUAD));
+        assert!(Channels::from_count(6) == Ok(Channels::SURROUND_5_1));
+        assert!(Channels::from_count(8) == Ok(Channels::SURROUND_7_1));
+        assert!(Channels::from_count(0) == Err(Error::UnsupportedChannelCount));
+        assert!(Channels::from_count(9) == Err(Error::UnsupportedChannelCount));
+    }
+
+    #[test]
+    fn channels_count() {
+        assert!(Channels::Mono.count() == 1);
+        assert!(Channels::Stereo.count() == 2);
+        assert!(Channels::QUAD.count() == 4);
+        assert!(Channels::SURROUND_5_1.count() == 6);
+        assert!(Channels::SURROUND_7_1.count() == 8);
+        assert!(u16::from(Channels::SURROUND_5_1) == 6);
+    }
+
+    #[test]
+    fn const_frame_size() {
+        const STEREO_I24: usize = frame_size(SampleFormat::I24, Channels::Stereo);
+        let buf = [0_u8; STEREO_I24];
+        assert!(buf.len() == 6);
+        assert!(frame_size(SampleFormat::U8, Channels::Mono) == 1);
+        assert!(frame_size(SampleFormat::I16, Channels::SURROUND_5_1) == 12);
+    }
+
+    #[test]
+    fn sample_from_format() {
+        let bytes = [0x12, 0x34];
+        assert!(i16::from_format(&bytes, SampleFormat::I16, Endian::Little) == 0x3412);
+        assert!(i16::from_format(&bytes, SampleFormat::I16, Endian::Big) == 0x1234);
+        assert!(i32::from_format(&bytes, SampleFormat::I16, Endian::Big) == 0x1234_0000);
+        assert!(u8::from_format(&bytes, SampleFormat::I16, Endian::Big) == 0x92);
+        assert!(i8::from_format(&[0x80], SampleFormat::U8, Endian::Little) == 0);
+
+        // floats outside -1.0..=1.0 aren't clipped
+        let bytes = 2.0_f32.to_le_bytes();
+        assert!(f32::from_format(&bytes, SampleFormat::F32, Endian::Little) == 2.0);
+        assert!(i16::from_format(&bytes, SampleFormat::F32, Endian::Little) == i16::MAX);
+
+        let bytes = (-0.25_f64).to_be_bytes();
+        assert!(f32::from_format(&bytes, SampleFormat::F64, Endian::Big) == -0.25);
+        assert!(i16::from_format(&bytes, SampleFormat::F64, Endian::Big) == -8192);
+    }
+
+    #[test]
+    fn slice_file() {
+        let mut wav = Wav::new(SliceFile::new(MONO_16BIT)).unwrap();
+        assert!(wav.total_frames() == 4);
+        let mut samples = [0_i16; 4];
+        assert!(wav.read_samples(&mut samples) == Ok(4));
+        assert!(samples == [1, -2, 2, -1]);
+
+        let mut file = wav.into_inner();
+        assert!(file.seek_from_end(4).is_ok());
+        assert!(file.seek_from_current(-100).is_err());
+        assert!(file.seek_from_start(MONO_16BIT.len() as u64 + 1).is_err());
     }
 }
@@ -1,8 +1,53 @@
-use heapless::Vec;
+use core::cell::RefCell;
 
-use crate::{AudioFile, Channels, PlatformFile, PlatformFileError, SampleFormat};
+use heapless::{String, Vec};
+
+use crate::{
+    AudioFile, Channels, Endian, OffsetFile, PlatformFile, PlatformFileError, Q15, SampleFormat,
+    convert, frame_size,
+};
+
+mod bext;
+mod builder;
+mod channel_reader;
+mod channel_view;
+mod cue;
+mod disp;
+mod frames;
+mod id3;
+#[cfg(feature = "embedded-sdmmc")]
+mod index;
+mod ms_adpcm;
+mod peak;
+mod ram;
+
+use bext::parse_bext;
+pub use bext::{BextInfo, MAX_CODING_HISTORY_LEN};
+pub use builder::WavBuilder;
+pub use channel_reader::ChannelReader;
+pub use channel_view::ChannelView;
+use cue::{CueLabel, parse_adtl, parse_cue};
+pub use cue::{CuePoint, MAX_CUE_POINTS, MAX_LABEL_LEN};
+pub use disp::MAX_DISPLAY_TITLE_LEN;
+use disp::parse_disp;
+pub use frames::{Frame, FrameIter};
+pub use id3::{ID3V1_FIELD_LEN, Id3v1};
+use id3::{ID3V1_LEN, parse_id3v1};
+#[cfg(feature = "embedded-sdmmc")]
+pub use index::index_directory;
+pub use ms_adpcm::MS_ADPCM_COEFFICIENTS;
+use ms_adpcm::{MsAdpcm, MsAdpcmFmt, parse_ms_adpcm};
+use peak::parse_peak;
+pub use peak::{Peak, PeakInfo};
+pub use ram::RamWav;
 
 const MAX_CHUNKS: usize = 25;
+/// Bytes of a fmt chunk that are parsed, enough for an extensible or MS ADPCM header
+const MAX_FMT_LEN: usize = 64;
+/// Bytes searched for the RIFF header by [`Wav::new_scan`]
+pub const MAX_SCAN_LEN: u64 = 4096;
+/// Maximum number of data chunks played back to back
+const MAX_DATA_CHUNKS: usize = 8;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
@@ -16,6 +61,8 @@ pub enum Error {
     NoFmtChunkFound,
     /// No Data chunk found
     NoDataChunkFound,
+    /// The file ends before the RIFF header, it's empty or truncated
+    FileTooShort,
     /// Failed to parse fmt chunk
     FmtChunkError,
     /// File contains unsupported Format
@@ -24,354 +71,4942 @@ pub enum Error {
     UnknownChunk,
     /// Unknown audio encoding
     UnknownEncoding,
+    /// Bit depth is impossible for the format tag, such as 16 bit float
+    FormatDepthMismatch,
+    /// The fmt byte rate isn't the sample rate times the block align
+    ByteRateMismatch,
     /// Unsupported channel count
     UnsupportedChannelCount,
+    /// Operation requires a different channel count
+    ChannelCountMismatch,
+    /// The channel index is past the last channel of the file
+    ChannelOutOfRange,
+    /// There's no cue point in the direction searched
+    NoCuePoint,
     /// The provided buffer is too small
     ChunkSizeIncorrect,
     /// Exceeded maximum chunks
     ExceededMaxChunks,
+    /// Data chunk extends past the end of the file
+    DataChunkOverflow,
+    /// A chunk other than data extends past the end of the file
+    ChunkOverflow,
+    /// A MS ADPCM block header names a predictor the fmt chunk doesn't define
+    AdpcmBlockError,
+    /// Position is outside of the data chunk
+    PositionOutOfBounds,
+    /// The read position is part way through a frame, see [`Wav::align_to_frame`]
+    MisalignedPosition,
+    /// The header continues past the provided bytes
+    NeedMoreData,
+    /// The file would need to seek but the backend can't
+    SeekUnsupported,
+    /// The read options don't work together for the file's format, see
+    /// [`WavBuilder::build`]
+    FormatMismatch,
     /// Platform File error
     PlatformError(PlatformFileError),
 }
 
+/// How strictly the file headers are validated while parsing
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum ParseMode {
+    /// Repair recoverable inconsistencies, such as clamping a data chunk to the file length
+    #[default]
+    Normal,
+    /// Reject any inconsistency with an error
+    Strict,
+    /// Repair like [`ParseMode::Normal`] and also accept non-standard files, such as chunk
+    /// tags written in the wrong case
+    Lenient,
+}
+
+/// What happens when seeking within the audio data fails
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum SeekPolicy {
+    /// Return the error to the caller
+    #[default]
+    Error,
+    /// Panic at the seek, for prototypes where a failed seek is unrecoverable
+    Panic,
+}
+
+/// What [`AudioFile::read`] returns once the end of the data is reached
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum EofMode {
+    /// Return `Ok(0)`
+    #[default]
+    Stop,
+    /// Start again from the first sample
+    Loop,
+    /// Return silent frames forever
+    Silence,
+}
+
+/// Which channel [`Wav::read_frames_packed`] puts in the high 16 bits of each word
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum PackOrder {
+    #[default]
+    LeftHigh,
+    RightHigh,
+}
+
+/// Audio properties and data location parsed from a header
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct WavProperties {
+    pub sample_rate: u16,
+    pub channels: Channels,
+    pub sample_format: SampleFormat,
+    /// file offset of the first sample
+    pub data_start: u64,
+    /// length of the audio data in bytes
+    pub data_len: u64,
+}
+
+/// Chunks needed for playback found by a forward pass over the file
+struct Header {
+    fmt: Fmt,
+    fmt_chunk: Chunk,
+    data_chunk: Chunk,
+    chunks: Vec<Chunk, MAX_CHUNKS>,
+}
+
+/// Outcome of [`Wav::try_open_partial`] on a file that may still be arriving
+// there's no allocator to box the parser, and it's returned by value like from `Wav::new`
+#[allow(clippy::large_enum_variant)]
+pub enum PartialOpen<File: PlatformFile> {
+    /// The fmt chunk and the start of the data chunk were found
+    Ready(Wav<File>),
+    /// The header continues past the end of the file, try again once at least
+    /// `bytes_needed` more bytes have arrived
+    NeedMore { file: File, bytes_needed: u64 },
+}
+
 /// Wav file parser
 pub struct Wav<File: PlatformFile> {
     file: File,
-    data_read: usize,
-    data_start: usize,
-    data_end: usize,
+    mode: ParseMode,
+    data_read: u64,
+    data_start: u64,
+    segments: Vec<Segment, MAX_DATA_CHUNKS>,
     fmt: Fmt,
+    fmt_chunk: Chunk,
+    chunks: Vec<Chunk, MAX_CHUNKS>,
+    seek_policy: SeekPolicy,
+    eof_mode: EofMode,
+    output_format: Option<SampleFormat>,
+    sample_endian: Endian,
+    gain: Option<Q15>,
+    dither: Option<convert::Dither>,
+    /// times a read failing with [`PlatformFileError::Io`] is tried again
+    retries: u8,
+    /// decoder state of a MS ADPCM file, `data_read` then counts encoded bytes
+    adpcm: Option<MsAdpcm>,
+    cue_points: Vec<CuePoint, MAX_CUE_POINTS>,
+    labels: Vec<CueLabel, MAX_CUE_POINTS>,
+    peak: Option<PeakInfo>,
+    display_title: Option<String<MAX_DISPLAY_TITLE_LEN>>,
+    bext: Option<BextInfo>,
+    #[cfg(feature = "diagnostics")]
+    diagnostics: Diagnostics,
 }
 
 impl<File: PlatformFile> Wav<File> {
-    pub fn new(mut file: File) -> Result<Self, Error> {
+    pub fn new(file: File) -> Result<Self, Error> {
+        Self::new_with_mode(file, ParseMode::default())
+    }
+
+    pub fn new_with_mode(mut file: File, mode: ParseMode) -> Result<Self, Error> {
         let mut chunks: Vec<Chunk, MAX_CHUNKS> = Vec::new();
         let mut buf = [0_u8; 64];
 
         // get riff before getting sub chunks
-        file.read(&mut buf).map_err(Error::PlatformError)?;
+        if read_full(&mut file, &mut buf).map_err(Error::PlatformError)? < 12 {
+            return Err(Error::FileTooShort);
+        }
         chunks
-            .push(parse_chunk(
-                buf[..8].try_into().map_err(|_| Error::ChunkSizeIncorrect)?,
-                0,
-            ))
+            .push(parse_chunk(buf[..8].try_into().unwrap(), 0))
             .unwrap();
 
         if chunks.first().unwrap().chunk != ChunkTag::Riff {
             return Err(Error::NoRiffChunkFound);
         }
 
-        parse_chunks(&mut buf, &mut file, &mut chunks, 12)?;
+        parse_chunks(&mut buf, &mut file, &mut chunks, 12, mode)?;
+        if mode == ParseMode::Lenient {
+            for chunk in chunks.iter_mut() {
+                if let ChunkTag::Unknown(bytes) = chunk.chunk {
+                    chunk.chunk = ChunkTag::from_bytes_ignore_case(&bytes);
+                }
+            }
+        }
 
-        let fmt_chunk = chunks
+        let fmt_chunk = *chunks
             .iter()
             .find(|chunk| chunk.chunk == ChunkTag::Fmt)
             .ok_or(Error::NoFmtChunkFound)?;
-        file.seek_from_start(fmt_chunk.start)
+        let fmt = read_fmt(&mut file, &fmt_chunk, mode)?;
+
+        // the samples can be split across several data chunks, with runs of silence between
+        // them, either at the top level or inside a `wavl` list
+        let mut data_chunks: Vec<Chunk, MAX_DATA_CHUNKS> = Vec::new();
+        for chunk in chunks.iter() {
+            match chunk.chunk {
+                ChunkTag::Data | ChunkTag::Slnt => data_chunks
+                    .push(*chunk)
+                    .map_err(|_| Error::ExceededMaxChunks)?,
+                ChunkTag::List => parse_wavl(&mut file, chunk, &mut data_chunks)?,
+                _ => {}
+            }
+        }
+
+        let file_len = file.length();
+        let mut segments: Vec<Segment, MAX_DATA_CHUNKS> = Vec::new();
+        for chunk in data_chunks {
+            let segment = match chunk.chunk {
+                ChunkTag::Slnt => {
+                    let mut frames = [0_u8; 4];
+                    if read_at(&mut file, chunk.start, &mut frames)? < frames.len() {
+                        return Err(Error::ChunkSizeIncorrect);
+                    }
+                    let frames = u32::from_le_bytes(frames) as u64;
+                    Segment {
+                        start: chunk.start,
+                        end: chunk.start,
+                        silence: frames * frame_size(fmt.sample_format, fmt.channels) as u64,
+                    }
+                }
+                _ => {
+                    // a truncated file can declare more data than it holds
+                    let mut end = chunk.start + chunk.data_len() as u64;
+                    if end > file_len {
+                        if mode == ParseMode::Strict {
+                            return Err(Error::DataChunkOverflow);
+                        }
+                        end = file_len.max(chunk.start);
+                    }
+                    Segment {
+                        start: chunk.start,
+                        end,
+                        silence: 0,
+                    }
+                }
+            };
+            segments
+                .push(segment)
+                .map_err(|_| Error::ExceededMaxChunks)?;
+        }
+        let data_start = segments.first().ok_or(Error::NoDataChunkFound)?.start;
+
+        // a data size covering the rest of the file can hide metadata written after the samples,
+        // look for it where the RIFF size says the samples end
+        if mode == ParseMode::Strict {
+            let riff_end = chunks[0].start + chunks[0].data_len() as u64;
+            if let Some(last) = segments.last_mut()
+                && last.start <= riff_end
+                && riff_end < last.end
+            {
+                last.end = riff_end;
+                let mut trailing: Vec<Chunk, MAX_CHUNKS> = Vec::new();
+                parse_chunks(&mut buf, &mut file, &mut trailing, riff_end, mode)?;
+                for chunk in trailing {
+                    if !matches!(chunk.chunk, ChunkTag::Unknown(_)) {
+                        chunks.push(chunk).map_err(|_| Error::ExceededMaxChunks)?;
+                    }
+                }
+            }
+            // samples can't run into the header of another chunk
+            for segment in segments.iter_mut() {
+                if let Some(header) = chunks
+                    .iter()
+                    .map(|chunk| chunk.start - 8)
+                    .filter(|header| (segment.start..segment.end).contains(header))
+                    .min()
+                {
+                    segment.end = header;
+                }
+            }
+        }
+
+        let mut cue_points = Vec::new();
+        let mut labels = Vec::new();
+        let mut peak = None;
+        let mut display_title = None;
+        let mut bext = None;
+        for chunk in chunks.iter() {
+            match chunk.chunk {
+                ChunkTag::Cue => cue_points = parse_cue(&mut file, chunk)?,
+                ChunkTag::List => parse_adtl(&mut file, chunk, &mut labels)?,
+                ChunkTag::Peak => peak = Some(parse_peak(&mut file, chunk, fmt.channels.count())?),
+                // files can carry the title as text and as an image, keep the first text
+                ChunkTag::Disp if display_title.is_none() => {
+                    display_title = parse_disp(&mut file, chunk)?
+                }
+                ChunkTag::Bext => bext = Some(parse_bext(&mut file, chunk)?),
+                _ => {}
+            }
+        }
+
+        file.seek_from_start(data_start)
             .map_err(Error::PlatformError)?;
-        file.read(&mut buf).map_err(Error::PlatformError)?;
-        let fmt = parse_fmt(&buf)?;
 
-        // TODO: can look for other chunks in list or info
+        let mut wav = Self::from_parts(file, fmt, fmt_chunk, segments, chunks);
+        wav.mode = mode;
+        wav.cue_points = cue_points;
+        wav.resolve_cue_frames();
+        wav.labels = labels;
+        wav.peak = peak;
+        wav.display_title = display_title;
+        wav.bext = bext;
+        Ok(wav)
+    }
+
+    /// parse a file front to back without seeking, for backends that can't seek
+    ///
+    /// parsing stops at the data chunk so the fmt chunk has to come before it, otherwise
+    /// [`Error::SeekUnsupported`] is returned. Chunks after the data chunk such as cue points
+    /// are not parsed.
+    pub fn new_streaming(mut file: File) -> Result<Self, Error> {
+        let header = Self::parse_forward(&mut file, false)?.ok_or(Error::SeekUnsupported)?;
+        Ok(Self::from_header(file, header, None))
+    }
+
+    /// parse a file in a single forward pass, stopping at the data chunk
+    ///
+    /// reads less than [`Wav::new`] for the common file where fmt comes right before data, and
+    /// falls back to [`Wav::new`] if the data chunk comes first. Chunks after the data chunk
+    /// such as cue points are only parsed by the fallback.
+    pub fn new_fast(mut file: File) -> Result<Self, Error> {
+        match Self::parse_forward(&mut file, true)? {
+            Some(header) => {
+                let file_len = file.length();
+                Ok(Self::from_header(file, header, Some(file_len)))
+            }
+            None => {
+                file.seek_from_start(0).map_err(Error::PlatformError)?;
+                Self::new(file)
+            }
+        }
+    }
+
+    /// read only the header of `file` to get its properties, without taking the file
+    ///
+    /// the file must be at its start and is left at the first sample. Files with the data
+    /// chunk before the fmt chunk need [`Wav::new`] and return [`Error::NoFmtChunkFound`]
+    pub fn probe(file: &mut File) -> Result<WavProperties, Error> {
+        let seek = file.can_seek();
+        let header = Self::parse_forward(file, seek)?.ok_or(Error::NoFmtChunkFound)?;
+        let data_end = match seek {
+            true => header.data_chunk.end.min(file.length()),
+            false => header.data_chunk.end,
+        };
+        Ok(WavProperties {
+            sample_rate: header.fmt.sample_rate,
+            channels: header.fmt.channels,
+            sample_format: header.fmt.sample_format,
+            data_start: header.data_chunk.start,
+            data_len: data_end - header.data_chunk.start,
+        })
+    }
+
+    /// check the headers of `file` for corruption without reading any samples
+    ///
+    /// returns the first problem found: a missing RIFF/WAVE header, an unparseable fmt chunk,
+    /// no data chunk or any chunk running past the end of the file. The file is rewound to its
+    /// start if it's valid
+    pub fn validate(file: &mut File) -> Result<(), Error> {
+        let mut chunks: Vec<Chunk, MAX_CHUNKS> = Vec::new();
+        let mut buf = [0_u8; 64];
+
+        file.seek_from_start(0).map_err(Error::PlatformError)?;
+        if read_full(file, &mut buf[..12]).map_err(Error::PlatformError)? < 12 {
+            return Err(Error::FileTooShort);
+        }
+        if ChunkTag::from_bytes(buf[..4].try_into().unwrap()) != ChunkTag::Riff {
+            return Err(Error::NoRiffChunkFound);
+        }
+        if ChunkTag::from_bytes(buf[8..12].try_into().unwrap()) != ChunkTag::Wave {
+            return Err(Error::NoWaveTagFound);
+        }
+        parse_chunks(&mut buf, file, &mut chunks, 12, ParseMode::Normal)?;
 
-        let data_chunk = chunks
+        let fmt_chunk = chunks
             .iter()
-            .find(|chunk| chunk.chunk == ChunkTag::Data)
-            .ok_or(Error::NoDataChunkFound)?;
-        file.seek_from_start(data_chunk.start)
-            .map_err(Error::PlatformError)?;
+            .find(|chunk| chunk.chunk == ChunkTag::Fmt)
+            .ok_or(Error::NoFmtChunkFound)?;
+        read_fmt(file, fmt_chunk, ParseMode::Normal)?;
+
+        // the samples can also be inside a `wavl` list
+        let mut data_chunks: Vec<Chunk, MAX_DATA_CHUNKS> = Vec::new();
+        for chunk in chunks.iter().filter(|chunk| chunk.chunk == ChunkTag::List) {
+            parse_wavl(file, chunk, &mut data_chunks)?;
+        }
+        if data_chunks.is_empty() && !chunks.iter().any(|chunk| chunk.chunk == ChunkTag::Data) {
+            return Err(Error::NoDataChunkFound);
+        }
 
-        Ok(Self {
+        let file_len = file.length();
+        for chunk in chunks.iter() {
+            if chunk.end <= file_len {
+                continue;
+            }
+            return Err(match chunk.chunk {
+                ChunkTag::Data => Error::DataChunkOverflow,
+                _ => Error::ChunkOverflow,
+            });
+        }
+
+        file.seek_from_start(0).map_err(Error::PlatformError)
+    }
+
+    /// parse chunk headers front to back until the data chunk, leaving the file at its first
+    /// sample
+    ///
+    /// returns `None` if the data chunk comes before the fmt chunk. Chunks are skipped by
+    /// reading them unless `seek` is set.
+    fn parse_forward(file: &mut File, seek: bool) -> Result<Option<Header>, Error> {
+        let mut chunks: Vec<Chunk, MAX_CHUNKS> = Vec::new();
+        let mut buf = [0_u8; 64];
+
+        if read_full(file, &mut buf[..12]).map_err(Error::PlatformError)? < 12 {
+            return Err(Error::FileTooShort);
+        }
+        chunks
+            .push(parse_chunk(buf[..8].try_into().unwrap(), 0))
+            .unwrap();
+        if chunks[0].chunk != ChunkTag::Riff {
+            return Err(Error::NoRiffChunkFound);
+        }
+        if ChunkTag::from_bytes(buf[8..12].try_into().unwrap()) != ChunkTag::Wave {
+            return Err(Error::NoWaveTagFound);
+        }
+
+        let mut fmt = None;
+        let mut offset = 12;
+        let mut header = [0_u8; 8];
+        // header bytes already read while parsing the previous chunk
+        let mut carried = 0;
+        loop {
+            if read_full(file, &mut header[carried..]).map_err(Error::PlatformError)?
+                < header.len() - carried
+            {
+                return Err(match fmt {
+                    Some(_) => Error::NoDataChunkFound,
+                    None => Error::NoFmtChunkFound,
+                });
+            }
+            carried = 0;
+            let mut chunk = parse_chunk(&header, offset);
+            chunks.push(chunk).map_err(|_| Error::ExceededMaxChunks)?;
+
+            let mut skip_len = chunk.end - chunk.start;
+            match chunk.chunk {
+                ChunkTag::Fmt => {
+                    let len = skip_len.min(buf.len() as u64) as usize;
+                    buf.fill(0);
+                    read_full(file, &mut buf[..len]).map_err(Error::PlatformError)?;
+                    skip_len -= len as u64;
+                    if len == 18 && buf[16..18] != [0, 0] {
+                        // bytes 16..18 may be the start of the next chunk's tag
+                        read_full(file, &mut buf[18..20]).map_err(Error::PlatformError)?;
+                        let after_base = buf[16..20].try_into().unwrap();
+                        if trim_missing_cb_size(&mut chunk, after_base) {
+                            *chunks.last_mut().unwrap() = chunk;
+                            header[..4].copy_from_slice(after_base);
+                            carried = 4;
+                        }
+                    }
+                    fmt = Some((
+                        parse_fmt(&buf, chunk.data_len() as u64, ParseMode::Normal)?,
+                        chunk,
+                    ));
+                }
+                ChunkTag::Data => {
+                    // the fmt chunk would need a seek back once it's found
+                    return Ok(fmt.map(|(fmt, fmt_chunk)| Header {
+                        fmt,
+                        fmt_chunk,
+                        data_chunk: Chunk {
+                            end: chunk.start + chunk.data_len() as u64,
+                            padded: false,
+                            ..chunk
+                        },
+                        chunks,
+                    }));
+                }
+                _ => {}
+            }
+
+            if seek {
+                carried = 0;
+                file.seek_from_start(chunk.end)
+                    .map_err(Error::PlatformError)?;
+            } else {
+                skip(file, skip_len)?;
+            }
+            offset = chunk.end;
+        }
+    }
+
+    /// assemble a parser from a forward pass, clamping the data chunk to `file_len` if known
+    fn from_header(file: File, header: Header, file_len: Option<u64>) -> Self {
+        let data_end = match file_len {
+            Some(file_len) => header.data_chunk.end.min(file_len),
+            None => header.data_chunk.end,
+        };
+        let mut segments = Vec::new();
+        segments
+            .push(Segment {
+                start: header.data_chunk.start,
+                end: data_end,
+                silence: 0,
+            })
+            .unwrap();
+        Self::from_parts(file, header.fmt, header.fmt_chunk, segments, header.chunks)
+    }
+
+    /// assemble a parser whose file is positioned at the first sample
+    fn from_parts(
+        file: File,
+        fmt: Fmt,
+        fmt_chunk: Chunk,
+        segments: Vec<Segment, MAX_DATA_CHUNKS>,
+        chunks: Vec<Chunk, MAX_CHUNKS>,
+    ) -> Self {
+        Self {
             file,
+            mode: ParseMode::default(),
+            adpcm: fmt.ms_adpcm.clone().map(MsAdpcm::new),
             fmt,
             data_read: 0,
-            data_start: data_chunk.start,
-            data_end: data_chunk.end,
-        })
+            data_start: segments[0].start,
+            segments,
+            fmt_chunk,
+            chunks,
+            seek_policy: SeekPolicy::default(),
+            eof_mode: EofMode::default(),
+            output_format: None,
+            sample_endian: Endian::Little,
+            gain: None,
+            dither: None,
+            retries: 0,
+            cue_points: Vec::new(),
+            labels: Vec::new(),
+            peak: None,
+            display_title: None,
+            bext: None,
+            #[cfg(feature = "diagnostics")]
+            diagnostics: Diagnostics::default(),
+        }
     }
-}
 
-impl<File: PlatformFile> AudioFile<File> for Wav<File> {
-    type Error = Error;
+    /// play a headerless file of raw samples in the given format, without parsing any RIFF
+    ///
+    /// the whole file up to its length is the data, `file` must be at its start
+    pub fn from_raw_pcm(
+        mut file: File,
+        sample_rate: u16,
+        channels: Channels,
+        sample_format: SampleFormat,
+    ) -> Self {
+        let audio_format = match sample_format {
+            SampleFormat::F32 | SampleFormat::F64 => AudioFormat::IeeeFloat,
+            _ => AudioFormat::Pcm,
+        };
+        let fmt = Fmt {
+            audio_format,
+            sample_rate,
+            channels,
+            sample_format,
+            extra: None,
+            channel_mask: None,
+            valid_bits: Some(sample_format.size() as u16 * 8),
+            ms_adpcm: None,
+            derived_sample_rate: Some(sample_rate as u32),
+            rate_consistent: true,
+        };
+        // there is no fmt chunk to copy out
+        let fmt_chunk = Chunk {
+            start: 0,
+            chunk: ChunkTag::Fmt,
+            end: 0,
+            padded: false,
+        };
 
-    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
-        // Total bytes in the data chunk
-        let data_len = self.data_end - self.data_start;
+        let mut segments = Vec::new();
+        segments
+            .push(Segment {
+                start: 0,
+                end: file.length(),
+                silence: 0,
+            })
+            .unwrap();
+        Self::from_parts(file, fmt, fmt_chunk, segments, Vec::new())
+    }
 
-        // How many bytes are left to read from the data chunk
-        let data_left = data_len.checked_sub(self.data_read).unwrap_or(0);
+    /// parse the properties from the start of a file that is already in memory
+    ///
+    /// returns [`Error::NeedMoreData`] if the fmt chunk or data chunk header lie past `header`
+    pub fn from_header_bytes(header: &[u8]) -> Result<WavProperties, Error> {
+        let riff = header.get(..12).ok_or(Error::NeedMoreData)?;
+        if ChunkTag::from_bytes(riff[..4].try_into().unwrap()) != ChunkTag::Riff {
+            return Err(Error::NoRiffChunkFound);
+        }
+        if ChunkTag::from_bytes(riff[8..12].try_into().unwrap()) != ChunkTag::Wave {
+            return Err(Error::NoWaveTagFound);
+        }
 
-        // If no more data left to read
-        if data_left == 0 {
-            return Ok(0);
+        let mut fmt = None;
+        let mut data = None;
+        let mut offset = 12;
+        loop {
+            let bytes = header.get(offset..offset + 8).ok_or(Error::NeedMoreData)?;
+            let mut chunk = parse_chunk(bytes.try_into().unwrap(), offset as u64);
+            match chunk.chunk {
+                ChunkTag::Fmt => {
+                    if chunk.end - chunk.start == 18 {
+                        let start = in_memory(chunk.start)?;
+                        let after_base = header
+                            .get(start + 16..start + 20)
+                            .ok_or(Error::NeedMoreData)?;
+                        trim_missing_cb_size(&mut chunk, after_base.try_into().unwrap());
+                    }
+                    let bytes = header
+                        .get(in_memory(chunk.start)?..in_memory(chunk.end)?)
+                        .ok_or(Error::NeedMoreData)?;
+                    if bytes.len() < 16 {
+                        return Err(Error::FmtChunkError);
+                    }
+                    fmt = Some(parse_fmt(bytes, bytes.len() as u64, ParseMode::Normal)?);
+                }
+                ChunkTag::Data => {
+                    data = Some(Chunk {
+                        end: chunk.start + chunk.data_len() as u64,
+                        padded: false,
+                        ..chunk
+                    })
+                }
+                _ => {}
+            }
+
+            if let (Some(fmt), Some(data)) = (&fmt, data) {
+                return Ok(WavProperties {
+                    sample_rate: fmt.sample_rate,
+                    channels: fmt.channels,
+                    sample_format: fmt.sample_format,
+                    data_start: data.start,
+                    data_len: data.end - data.start,
+                });
+            }
+            offset = in_memory(chunk.end)?;
         }
+    }
 
-        // Limit the read to the amount of data left
-        let to_read = buf.len().min(data_left);
+    /// parse a file that is still being downloaded as soon as its header has arrived
+    ///
+    /// the file is handed back with [`PartialOpen::NeedMore`] while the fmt chunk or the
+    /// data chunk header are missing. Once ready the samples that haven't arrived yet are
+    /// left out of the data, see [`Wav::refresh_length`]
+    pub fn try_open_partial(mut file: File) -> Result<PartialOpen<File>, Error> {
+        let len = file.length();
+        let need_more = |file, end: u64| {
+            Ok(PartialOpen::NeedMore {
+                file,
+                bytes_needed: end - len,
+            })
+        };
+        if len < 12 {
+            return need_more(file, 12);
+        }
+
+        let mut riff = [0_u8; 12];
+        read_at(&mut file, 0, &mut riff)?;
+        if ChunkTag::from_bytes(riff[..4].try_into().unwrap()) != ChunkTag::Riff {
+            return Err(Error::NoRiffChunkFound);
+        }
 
-        // Perform the read
-        match self.file.read(&mut buf[..to_read]) {
-            Ok(len) => {
-                self.data_read += len;
-                Ok(len)
+        let mut fmt_found = false;
+        let mut offset = 12;
+        loop {
+            if offset + 8 > len {
+                return need_more(file, offset + 8);
             }
-            Err(e) => Err(Error::PlatformError(e)),
+            let mut header = [0_u8; 8];
+            read_at(&mut file, offset, &mut header)?;
+            let chunk = parse_chunk(&header, offset);
+            match chunk.chunk {
+                ChunkTag::Fmt if chunk.end > len => return need_more(file, chunk.end),
+                ChunkTag::Fmt => fmt_found = true,
+                ChunkTag::Data if fmt_found => break,
+                _ => {}
+            }
+            offset = chunk.end;
         }
+
+        file.seek_from_start(0).map_err(Error::PlatformError)?;
+        Ok(PartialOpen::Ready(Self::new(file)?))
     }
 
-    fn sample_rate(&self) -> u16 {
-        self.fmt.sample_rate
+    /// get back the file, positioned after the last sample read
+    pub fn into_inner(self) -> File {
+        self.file
     }
 
-    fn channels(&self) -> Channels {
-        self.fmt.channels
+    /// borrow the file
+    pub fn get_ref(&self) -> &File {
+        &self.file
     }
 
-    fn sample_format(&self) -> SampleFormat {
-        self.fmt.sample_format
+    /// mutably borrow the file
+    ///
+    /// moving the file desyncs the read position, see [`Wav::sync_position`]
+    pub fn get_mut(&mut self) -> &mut File {
+        &mut self.file
     }
 
-    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
-        let byte_offset = sample_offset * self.sample_format().size() as i64;
-        self.file
-            .seek_from_current(byte_offset)
-            .map_err(Error::PlatformError)
+    /// get every chunk found in the file, starting with the RIFF chunk
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
     }
 
-    fn is_eof(&self) -> bool {
-        self.data_end == self.data_read
+    /// get a reader bounded to the data of `chunk`
+    ///
+    /// the audio read position is restored when the reader is dropped
+    pub fn chunk_reader(&mut self, chunk: &Chunk) -> Result<ChunkReader<'_, File>, Error> {
+        self.file
+            .seek_from_start(chunk.start)
+            .map_err(Error::PlatformError)?;
+        Ok(ChunkReader {
+            pos: chunk.start,
+            end: chunk.end,
+            wav: self,
+        })
     }
 
-    fn played(&self) -> usize {
-        self.data_read
+    /// iterate over the frames left to read, each converted to 16 bit
+    pub fn frames(&mut self) -> FrameIter<'_, File> {
+        FrameIter::new(self)
     }
-}
 
-/// parses the file in the first pass to find out where each chunk is located
-fn parse_chunks<File: PlatformFile, const MAX_CHUNKS: usize>(
-    buf: &mut [u8],
-    file: &mut File,
-    chunks: &mut Vec<Chunk, MAX_CHUNKS>,
-    file_offset: usize,
-) -> Result<(), Error> {
-    file.seek_from_start(file_offset)
-        .map_err(Error::PlatformError)?;
-    let read_len = match file.read(buf) {
-        Ok(len) => len,
-        Err(PlatformFileError::EOF) => return Ok(()),
-        Err(e) => return Err(Error::PlatformError(e)),
-    };
+    /// read all of the samples into RAM, so short sounds that play often don't go back to the
+    /// file
+    ///
+    /// the samples are read as they'd be played, in the output format and with any gain.
+    /// Returns [`Error::ChunkSizeIncorrect`] if they don't fit in `N` bytes
+    pub fn load_to_ram<const N: usize>(mut self) -> Result<RamWav<File, N>, Error> {
+        let len = self.data_len();
+        if len > N {
+            return Err(Error::ChunkSizeIncorrect);
+        }
+        self.restart()?;
 
-    if read_len == 0 {
-        return Ok(()); // EOF
+        let mut data = [0_u8; N];
+        let mut read = 0;
+        while read < len {
+            match self.read(&mut data[read..len])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+        Ok(RamWav::new(
+            data,
+            read,
+            self.sample_rate(),
+            self.channels(),
+            self.sample_format(),
+        ))
     }
 
-    let mut index = 0;
+    /// read only channel `idx` as a mono source, counting from 0
+    ///
+    /// returns [`Error::ChannelOutOfRange`] if the file has no such channel
+    pub fn channel(&mut self, idx: usize) -> Result<ChannelReader<'_, File>, Error> {
+        if idx >= self.channels().count() as usize {
+            return Err(Error::ChannelOutOfRange);
+        }
+        Ok(ChannelReader::new(self, idx))
+    }
 
-    while index + 8 <= read_len {
-        chunks
-            .push(parse_chunk(
-                &buf[index..index + 8]
-                    .try_into()
-                    .map_err(|_| Error::ChunkSizeIncorrect)?,
-                file_offset + index,
-            ))
-            .map_err(|_| Error::ExceededMaxChunks)?;
+    /// read whole frames from the current position into `buf`, keeping only the samples of
+    /// `channel`, returns the bytes written
+    ///
+    /// frames are read a few at a time through a small buffer rather than seeking to each
+    /// sample, so output conversions and compressed data still work and a slow backend
+    /// isn't asked for a few bytes at a time
+    fn read_channel(&mut self, channel: usize, buf: &mut [u8]) -> Result<usize, Error> {
+        let sample_size = self.sample_format().size() as usize;
+        let frame_size = self.frame_size();
 
-        let last_chunk = chunks.last().unwrap();
-        let chunk_len = last_chunk.end - last_chunk.start + 8;
+        let mut scratch = [0_u8; 64];
+        let chunk_len = scratch.len() - scratch.len() % frame_size;
+        let mut read = 0;
+        while read + sample_size <= buf.len() {
+            let want = ((buf.len() - read) / sample_size * frame_size).min(chunk_len);
+            let len = self.read(&mut scratch[..want])?;
+            if len == 0 {
+                break;
+            }
+            for frame in scratch[..len].chunks_exact(frame_size) {
+                let sample = &frame[channel * sample_size..][..sample_size];
+                buf[read..read + sample_size].copy_from_slice(sample);
+                read += sample_size;
+            }
+        }
+        Ok(read)
+    }
 
-        if index + chunk_len <= read_len {
-            index += chunk_len;
-        } else {
-            return parse_chunks(buf, file, chunks, chunks.last().unwrap().end);
+    /// split a stereo file into left and right mono views that share the file
+    pub fn split_channels(
+        wav: &RefCell<Self>,
+    ) -> Result<(ChannelView<'_, File>, ChannelView<'_, File>), Error> {
+        if wav.borrow().channels() != Channels::Stereo {
+            return Err(Error::ChannelCountMismatch);
         }
+        Ok((ChannelView::new(wav, 0), ChannelView::new(wav, 1)))
     }
-    parse_chunks(buf, file, chunks, file_offset + read_len)
-}
 
-fn parse_chunk(bytes: &[u8; 8], index: usize) -> Chunk {
-    let tag = ChunkTag::from_bytes(&bytes[..4].try_into().unwrap());
-    let mut chunk_len = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+    /// get the cue points from the `cue ` chunk
+    pub fn cue_points(&self) -> &[CuePoint] {
+        &self.cue_points
+    }
 
-    // padding if chunk_len is odd (RIFF word alignment)
-    if chunk_len % 2 != 0 {
-        chunk_len += 1;
+    /// find the frame of each cue point from the data chunk it points into
+    ///
+    /// a cue point whose `chunk_start` matches no data chunk is taken as counting from the
+    /// first one, which is what writers that leave the field at 0 mean
+    fn resolve_cue_frames(&mut self) {
+        let first = self.segments[0].start;
+        let frame_size = self.source_frame_size() as u64;
+        for cue in self.cue_points.iter_mut() {
+            let index = self
+                .segments
+                .iter()
+                .position(|segment| segment.start - first == cue.chunk_start as u64)
+                .unwrap_or(0);
+            // the decoded frames of compressed data chunks aren't known without decoding
+            let frames_before: u64 = match self.adpcm {
+                Some(_) => 0,
+                None => self.segments[..index]
+                    .iter()
+                    .map(|segment| segment.len() / frame_size)
+                    .sum(),
+            };
+            cue.frame = (frames_before + cue.sample_offset as u64) as usize;
+        }
     }
 
-    Chunk {
-        chunk: tag,
-        start: index + 8,
-        end: index + 8 + chunk_len,
+    /// move to the first cue point after the read position, for skipping to the next chapter
+    ///
+    /// returns the cue point moved to, or [`Error::NoCuePoint`] if there's none after it
+    pub fn seek_next_cue(&mut self) -> Result<CuePoint, Error> {
+        let current = self.current_frame();
+        let cue = self
+            .cue_points
+            .iter()
+            .filter(|cue| cue.frame > current)
+            .min_by_key(|cue| cue.frame)
+            .copied()
+            .ok_or(Error::NoCuePoint)?;
+        self.seek_to_cue(&cue)?;
+        Ok(cue)
     }
-}
 
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
-pub enum ChunkTag {
-    Riff,
-    Wave,
-    Fmt,
-    Data,
-    Unknown([u8; 4]),
-}
+    /// move to the last cue point before the read position, for going back a chapter
+    ///
+    /// returns the cue point moved to, or [`Error::NoCuePoint`] if there's none before it
+    pub fn seek_prev_cue(&mut self) -> Result<CuePoint, Error> {
+        let current = self.current_frame();
+        let cue = self
+            .cue_points
+            .iter()
+            .filter(|cue| cue.frame < current)
+            .max_by_key(|cue| cue.frame)
+            .copied()
+            .ok_or(Error::NoCuePoint)?;
+        self.seek_to_cue(&cue)?;
+        Ok(cue)
+    }
 
-impl ChunkTag {
-    fn from_bytes(bytes: &[u8; 4]) -> Self {
-        match bytes {
-            [b'R', b'I', b'F', b'F'] => Self::Riff,
-            [b'W', b'A', b'V', b'E'] => Self::Wave,
-            [b'd', b'a', b't', b'a'] => Self::Data,
-            [b'f', b'm', b't', b' '] => Self::Fmt,
-            _ => Self::Unknown(*bytes),
-        }
+    fn current_frame(&self) -> usize {
+        self.played() / self.channels().count() as usize
     }
-}
 
-#[derive(Copy, Clone, Debug)]
-pub struct Chunk {
-    /// start of chunk data after chunk tag and len
-    pub start: usize,
-    /// chunk tag/id
-    pub chunk: ChunkTag,
-    /// end of the chunk
-    pub end: usize,
-}
+    fn seek_to_cue(&mut self, cue: &CuePoint) -> Result<(), Error> {
+        let channels = self.channels().count() as i64;
+        let frames = cue.frame as i64 - self.current_frame() as i64;
+        self.try_seek(frames * channels)
+    }
 
-struct Fmt {
-    audio_format: AudioFormat,
-    sample_rate: u16,
-    channels: Channels,
-    sample_format: SampleFormat,
-    extra: Option<ExtraFmtParam>,
-}
+    /// get the `labl` text attached to a cue point, such as a chapter title
+    pub fn label_for_cue(&self, id: u32) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|label| label.id == id)
+            .map(|label| label.text.as_str())
+    }
 
-struct ExtraFmtParam {
-    param_size: u16,
-    // params: &[]
-}
+    /// get the byte offset of the first sample from the start of the file
+    ///
+    /// useful to check whether a `JUNK` chunk lined the samples up with a sector boundary
+    pub fn data_start_offset(&self) -> u64 {
+        self.data_start
+    }
 
-#[derive(PartialEq, Eq)]
-enum AudioFormat {
-    Pcm,
-}
+    /// get the byte offset of the read position within the samples, to check a seek landed
+    /// where expected
+    ///
+    /// counts the stored bytes, before any output conversion. For MS ADPCM that's encoded
+    /// bytes, and any silence of a `slnt` chunk counts as samples
+    pub fn position_in_data(&self) -> u64 {
+        self.data_read
+    }
 
-impl AudioFormat {
-    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
-        let format = u16::from_le_bytes(bytes.try_into().map_err(|_| Error::ChunkSizeIncorrect)?);
-        match format {
-            1 => Ok(Self::Pcm),
-            _ => Err(Error::UnsupportedAudioFormat),
+    /// pick up samples appended to the file since it was parsed, such as a recording that's
+    /// still being written
+    ///
+    /// only a last data chunk that declares more bytes than the file held when parsed grows,
+    /// and only by whole frames (or blocks) up to its declared size
+    pub fn refresh_length(&mut self) -> Result<(), Error> {
+        let Some(last) = self.segments.last_mut() else {
+            return Ok(());
+        };
+        let Some(chunk) = self
+            .chunks
+            .iter()
+            .find(|chunk| chunk.chunk == ChunkTag::Data && chunk.start == last.start)
+        else {
+            return Ok(());
+        };
+        let declared_end = chunk.start + chunk.data_len() as u64;
+        if last.silence != 0 || last.end >= declared_end {
+            return Ok(());
         }
+
+        let unit = match &self.adpcm {
+            Some(adpcm) => adpcm.block_align(),
+            None => frame_size(self.fmt.sample_format, self.fmt.channels),
+        } as u64;
+        let available = declared_end.min(self.file.length()).max(last.end) - last.start;
+        last.end = last.start + available - available % unit;
+        Ok(())
     }
-}
 
-fn parse_fmt(buf: &[u8]) -> Result<Fmt, Error> {
-    let format = AudioFormat::from_bytes(&buf[0..2])?;
+    /// get the sample rate implied by the fmt chunk's byte rate and block align
+    ///
+    /// `None` for compressed data, where the two aren't tied to the sample rate
+    pub fn derived_sample_rate(&self) -> Option<u32> {
+        self.fmt.derived_sample_rate
+    }
 
-    let num_channels = u16::from_le_bytes(
-        buf[2..4]
-            .try_into()
-            .map_err(|_| Error::ChunkSizeIncorrect)?,
-    );
-    let channels = match num_channels {
-        1 => Channels::Mono,
-        2 => Channels::Stereo,
-        _ => return Err(Error::UnsupportedChannelCount),
-    };
+    /// check the declared sample rate agrees with [`Wav::derived_sample_rate`]
+    ///
+    /// [`AudioFile::sample_rate`] always returns the declared rate, files from buggy encoders
+    /// can get the byte rate wrong
+    pub fn rate_consistent(&self) -> bool {
+        self.fmt.rate_consistent
+    }
 
-    let sample_rate = u32::from_le_bytes(
-        buf[4..8]
-            .try_into()
-            .map_err(|_| Error::ChunkSizeIncorrect)?,
-    ) as u16;
-    let bit_depth = u16::from_le_bytes(
-        buf[14..16]
-            .try_into()
-            .map_err(|_| Error::ChunkSizeIncorrect)?,
-    );
+    /// get the bytes of stored audio data across every data chunk, before decoding
+    ///
+    /// 64 bit like file offsets, runs of `slnt` silence aren't stored so they aren't counted
+    pub fn audio_bytes(&self) -> u64 {
+        self.segments.iter().map(|s| s.end - s.start).sum()
+    }
 
-    let encoding = match bit_depth {
-        8 => SampleFormat::U8,
-        16 => SampleFormat::I16,
-        24 => SampleFormat::I24,
-        _ => return Err(Error::UnknownEncoding),
-    };
+    /// get the bytes of the file that aren't audio data, such as headers and metadata
+    pub fn overhead_bytes(&mut self) -> u64 {
+        self.file.length().saturating_sub(self.audio_bytes())
+    }
 
-    Ok(Fmt {
-        audio_format: format,
-        sample_rate,
-        channels,
-        sample_format: encoding,
-        extra: None,
-    })
-}
+    /// get the per channel peaks from the `PEAK` chunk
+    pub fn peak(&self) -> Option<&PeakInfo> {
+        self.peak.as_ref()
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::{AudioFormat, Wav};
-    use crate::{AudioFile, Channels, SampleFormat, TestFile, wav::Error};
+    /// get how many reads returned fewer whole frames than the buffer holds before EOF
+    #[cfg(feature = "diagnostics")]
+    pub fn short_reads(&self) -> u32 {
+        self.diagnostics.short_reads
+    }
 
-    #[test]
-    fn parse_fmt() {
-        let bytes = [
+    /// get how many times [`AudioFile::read`] was called
+    #[cfg(feature = "diagnostics")]
+    pub fn total_reads(&self) -> u32 {
+        self.diagnostics.total_reads
+    }
+
+    /// get the title to display from a text `DISP` chunk
+    pub fn display_title(&self) -> Option<&str> {
+        self.display_title.as_deref()
+    }
+
+    /// get the broadcast extension, such as the UMID and coding history
+    pub fn bext(&self) -> Option<&BextInfo> {
+        self.bext.as_ref()
+    }
+
+    /// get the bits of each stored sample that hold audio, less than the container in
+    /// WAVE_FORMAT_EXTENSIBLE files such as 24 valid bits in 32 bit samples
+    ///
+    /// `None` for compressed data, the padding bits below the valid ones are cleared when
+    /// converting samples
+    pub fn valid_bits(&self) -> Option<u16> {
+        self.fmt.valid_bits
+    }
+
+    /// get the speaker positions, only present in WAVE_FORMAT_EXTENSIBLE files
+    pub fn speaker_layout(&self) -> Option<SpeakerLayout> {
+        self.fmt.channel_mask.map(SpeakerLayout::from_bits)
+    }
+
+    /// convert samples to `format` as they are read
+    ///
+    /// [`AudioFile::sample_format`] then reports `format` instead of the source format
+    pub fn set_output_format(&mut self, format: SampleFormat) {
+        self.output_format = Some(format);
+    }
+
+    /// scale samples by `gain` as they are read, before any dither
+    pub fn set_gain(&mut self, gain: Q15) {
+        self.gain = Some(gain);
+    }
+
+    /// try a read of the samples up to `count` more times if the file fails with
+    /// [`PlatformFileError::Io`], such as an SD card with transient errors
+    ///
+    /// other errors and the end of the file are returned straight away
+    pub fn set_retry(&mut self, count: u8) {
+        self.retries = count;
+    }
+
+    /// add TPDF dither before truncating samples converted to a smaller [`SampleFormat`]
+    ///
+    /// the noise comes from a generator seeded the same way for every file, so output is
+    /// reproducible. Conversions that keep or increase the bit depth are unaffected
+    pub fn set_dither(&mut self, enabled: bool) {
+        self.dither = enabled.then(convert::Dither::new);
+    }
+
+    /// override the byte order the samples are stored in, the header is always little endian
+    ///
+    /// this is an escape hatch for files mislabeled by buggy encoders, samples returned by
+    /// [`AudioFile::read`] are always converted to little endian
+    pub fn with_sample_endian(mut self, endian: Endian) -> Self {
+        self.sample_endian = endian;
+        self
+    }
+
+    /// choose how [`AudioFile::try_seek`] reports failures
+    pub fn set_seek_policy(&mut self, policy: SeekPolicy) {
+        self.seek_policy = policy;
+    }
+
+    /// choose what reading past the last sample does
+    pub fn set_eof_mode(&mut self, mode: EofMode) {
+        self.eof_mode = mode;
+    }
+
+    /// realign the read position after the underlying file was moved externally
+    ///
+    /// `bytes_into_data` is the file's new position relative to the first sample, MS ADPCM
+    /// files must be moved to the start of a block
+    pub fn sync_position(&mut self, bytes_into_data: u64) -> Result<(), Error> {
+        if bytes_into_data > self.data_bytes() {
+            return Err(Error::PositionOutOfBounds);
+        }
+        if let Some(adpcm) = &mut self.adpcm {
+            if !bytes_into_data.is_multiple_of(adpcm.block_align() as u64) {
+                return Err(Error::PositionOutOfBounds);
+            }
+            adpcm.clear();
+        }
+        self.data_read = bytes_into_data;
+        Ok(())
+    }
+
+    /// move the read position forward to the next frame boundary, after
+    /// [`Wav::sync_position`] was given an offset part way through a frame
+    ///
+    /// reads never stop part way through a frame, so only moving the position by hand needs
+    /// this
+    pub fn align_to_frame(&mut self) -> Result<(), Error> {
+        let frame_size = self.source_frame_size() as u64;
+        let offset = self.data_read % frame_size;
+        if self.adpcm.is_some() || offset == 0 {
+            return Ok(());
+        }
+
+        let aligned = (self.data_read + frame_size - offset).min(self.data_bytes());
+        self.file
+            .seek_from_start(self.file_offset(aligned))
+            .map_err(Error::PlatformError)?;
+        self.data_read = aligned;
+        Ok(())
+    }
+
+    /// read whole stereo frames as (left, right) pairs converted to 16 bit
+    ///
+    /// returns the number of frames read
+    pub fn read_frames_stereo(&mut self, out: &mut [(i16, i16)]) -> Result<usize, Error> {
+        if self.channels() != Channels::Stereo {
+            return Err(Error::ChannelCountMismatch);
+        }
+
+        let format = self.sample_format();
+        let sample_size = format.size() as usize;
+        let frame_size = self.frame_size();
+        let mut buf = [0_u8; 64];
+        let chunk_len = buf.len() - buf.len() % frame_size;
+
+        let mut frames = 0;
+        while frames < out.len() {
+            let want = ((out.len() - frames) * frame_size).min(chunk_len);
+            let len = self.read(&mut buf[..want])?;
+            if len < frame_size {
+                break;
+            }
+            for (frame, bytes) in out[frames..]
+                .iter_mut()
+                .zip(buf[..len].chunks_exact(frame_size))
+            {
+                *frame = (
+                    format.to_i16(&bytes[..sample_size]),
+                    format.to_i16(&bytes[sample_size..]),
+                );
+                frames += 1;
+            }
+        }
+        Ok(frames)
+    }
+
+    /// read whole frames with each channel converted to 16 bit into its own buffer, in
+    /// channel order
+    ///
+    /// there has to be one buffer per channel, frames stop at the shortest buffer. Returns
+    /// the number of frames read
+    pub fn read_planar_i16(&mut self, channels: &mut [&mut [i16]]) -> Result<usize, Error> {
+        if channels.len() != self.channels().count() as usize {
+            return Err(Error::ChannelCountMismatch);
+        }
+
+        let format = self.sample_format();
+        let sample_size = format.size() as usize;
+        let frame_size = self.frame_size();
+        let mut buf = [0_u8; 64];
+        let chunk_len = buf.len() - buf.len() % frame_size;
+
+        let wanted = channels
+            .iter()
+            .map(|channel| channel.len())
+            .min()
+            .unwrap_or(0);
+        let mut frames = 0;
+        while frames < wanted {
+            let want = ((wanted - frames) * frame_size).min(chunk_len);
+            let len = self.read(&mut buf[..want])?;
+            if len < frame_size {
+                break;
+            }
+            for bytes in buf[..len].chunks_exact(frame_size) {
+                for (channel, sample) in channels.iter_mut().zip(bytes.chunks_exact(sample_size)) {
+                    channel[frames] = format.to_i16(sample);
+                }
+                frames += 1;
+            }
+        }
+        Ok(frames)
+    }
+
+    /// read whole stereo frames packed into one word each for an I2S peripheral, both channels
+    /// converted to 16 bit and `order` choosing which goes in the high half
+    ///
+    /// returns the number of frames read
+    pub fn read_frames_packed(
+        &mut self,
+        out: &mut [u32],
+        order: PackOrder,
+    ) -> Result<usize, Error> {
+        let mut frames = [(0_i16, 0_i16); 16];
+        let mut packed = 0;
+        while packed < out.len() {
+            let want = (out.len() - packed).min(frames.len());
+            let len = self.read_frames_stereo(&mut frames[..want])?;
+            if len == 0 {
+                break;
+            }
+            for (word, (left, right)) in out[packed..].iter_mut().zip(&frames[..len]) {
+                let (high, low) = match order {
+                    PackOrder::LeftHigh => (left, right),
+                    PackOrder::RightHigh => (right, left),
+                };
+                *word = (*high as u16 as u32) << 16 | *low as u16 as u32;
+            }
+            packed += len;
+        }
+        Ok(packed)
+    }
+
+    /// read up to `frames` frames, handing them to `f` one internal buffer at a time
+    ///
+    /// returns the number of frames delivered, which is less than `frames` at the end of the data
+    pub fn fill<F: FnMut(&[u8])>(&mut self, frames: usize, mut f: F) -> Result<usize, Error> {
+        let frame_size = self.frame_size();
+        let mut buf = [0_u8; 64];
+        let chunk_len = buf.len() - buf.len() % frame_size;
+
+        let mut delivered = 0;
+        while delivered < frames {
+            let want = ((frames - delivered) * frame_size).min(chunk_len);
+            let len = self.read(&mut buf[..want])?;
+            if len == 0 {
+                break;
+            }
+            f(&buf[..len]);
+            delivered += len / frame_size;
+        }
+        Ok(delivered)
+    }
+
+    /// count the frames by reading through the data chunk, the read position is left unchanged
+    ///
+    /// a data chunk declared with a size of 0 is treated as running to the end of the file
+    pub fn count_frames(&mut self) -> Result<usize, Error> {
+        let unknown_size = [Segment {
+            start: self.data_start,
+            end: self.file.length(),
+            silence: 0,
+        }];
+        let segments = match self.data_bytes() {
+            0 => &unknown_size[..],
+            _ => &self.segments[..],
+        };
+
+        let mut buf = [0_u8; 64];
+        let mut bytes = 0;
+        for segment in segments {
+            self.file
+                .seek_from_start(segment.start)
+                .map_err(Error::PlatformError)?;
+            let mut offset = segment.start;
+            while offset < segment.end {
+                let to_read = (buf.len() as u64).min(segment.end - offset) as usize;
+                let len =
+                    read_full(&mut self.file, &mut buf[..to_read]).map_err(Error::PlatformError)?;
+                if len == 0 {
+                    break;
+                }
+                offset += len as u64;
+            }
+            bytes += offset - segment.start + segment.silence;
+        }
+        self.file
+            .seek_from_start(self.file_offset(self.data_read))
+            .map_err(Error::PlatformError)?;
+
+        Ok((self.decoded_len(bytes) / self.source_frame_size() as u64) as usize)
+    }
+
+    /// summarize the data as the (min, max) sample of `buckets.len()` equal windows of frames
+    ///
+    /// windows span every channel, the read position is left unchanged
+    pub fn waveform_overview(&mut self, buckets: &mut [(i16, i16)]) -> Result<(), Error> {
+        if buckets.is_empty() {
+            return Ok(());
+        }
+        buckets.fill((i16::MAX, i16::MIN));
+        // kept in u64 as frames times buckets overflows usize on 32 bit targets
+        let total_frames = self.source_bytes() / self.source_frame_size() as u64;
+        let format = self.fmt.sample_format;
+        let sample_size = format.size() as usize;
+
+        let mut frame = 0_u64;
+        let result = self.scan_frames(|bytes| {
+            let (min, max) = &mut buckets[(frame * buckets.len() as u64 / total_frames) as usize];
+            for sample in bytes.chunks_exact(sample_size) {
+                let sample = format.to_i16(sample);
+                *min = (*min).min(sample);
+                *max = (*max).max(sample);
+            }
+            frame += 1;
+        });
+
+        // windows without any frames are silent
+        for bucket in buckets.iter_mut() {
+            if bucket.0 > bucket.1 {
+                *bucket = (0, 0);
+            }
+        }
+        result
+    }
+
+    /// estimate the loudness in dBFS as the mean square of every frame above a silence gate
+    ///
+    /// this is a rough approximation for leveling files against each other, not an EBU R128
+    /// measurement: there is no frequency weighting and the gate is a fixed -70 dBFS per
+    /// frame. Returns negative infinity if every frame is silent, the read position is left
+    /// unchanged
+    pub fn loudness_estimate(&mut self) -> Result<f32, Error> {
+        // mean square of a frame at -70 dBFS
+        const SILENCE_GATE: f32 = 1e-7;
+
+        let format = self.fmt.sample_format;
+        let sample_size = format.size() as usize;
+        let channels = self.fmt.channels.count() as f32;
+
+        let mut sum = 0.0_f64;
+        let mut frames = 0_u64;
+        self.scan_frames(|bytes| {
+            let mean_square = bytes
+                .chunks_exact(sample_size)
+                .map(|sample| {
+                    let sample = convert::i32_to_f32(format.to_i32(sample));
+                    sample * sample
+                })
+                .sum::<f32>()
+                / channels;
+            if mean_square >= SILENCE_GATE {
+                sum += mean_square as f64;
+                frames += 1;
+            }
+        })?;
+
+        Ok(match frames {
+            0 => f32::NEG_INFINITY,
+            _ => 10.0 * log10((sum / frames as f64) as f32),
+        })
+    }
+
+    /// count the samples at the format's full scale minimum or maximum, a sign of clipping
+    ///
+    /// float samples count if they are at or beyond full scale, the read position is left
+    /// unchanged
+    pub fn clip_count(&mut self) -> Result<usize, Error> {
+        let format = self.fmt.sample_format;
+        let sample_size = format.size() as usize;
+        // widened samples only use the top bits of an i32
+        let max = match sample_size {
+            4.. => i32::MAX,
+            size => i32::MAX & !((1 << (32 - 8 * size)) - 1),
+        };
+
+        let mut clipped = 0;
+        self.scan_frames(|bytes| {
+            clipped += bytes
+                .chunks_exact(sample_size)
+                .map(|sample| format.to_i32(sample))
+                .filter(|sample| *sample == i32::MIN || *sample >= max)
+                .count();
+        })?;
+        Ok(clipped)
+    }
+
+    /// CRC-32 (IEEE) of the bytes stored in the data chunks, to check the samples against a
+    /// checksum kept with the file
+    ///
+    /// metadata chunks aren't covered and neither is the silence of `slnt` chunks. The read
+    /// position is left unchanged
+    pub fn data_crc32(&mut self) -> Result<u32, Error> {
+        let mut crc = !0_u32;
+        let mut buf = [0_u8; 64];
+        let mut result = Ok(());
+        'segments: for i in 0..self.segments.len() {
+            let segment = self.segments[i];
+            let mut offset = segment.start;
+            while offset < segment.end {
+                let len = (segment.end - offset).min(buf.len() as u64) as usize;
+                match read_at(&mut self.file, offset, &mut buf[..len]) {
+                    Ok(0) => break,
+                    Ok(read) => {
+                        crc = crc32_update(crc, &buf[..read]);
+                        offset += read as u64;
+                    }
+                    Err(e) => {
+                        result = Err(e);
+                        break 'segments;
+                    }
+                }
+            }
+        }
+
+        self.file
+            .seek_from_start(self.file_offset(self.data_read))
+            .map_err(Error::PlatformError)?;
+        result.map(|()| !crc)
+    }
+
+    /// hand every frame of stored samples to `f` in little endian, from the first sample on
+    ///
+    /// the read position is left unchanged
+    fn scan_frames<F: FnMut(&[u8])>(&mut self, mut f: F) -> Result<(), Error> {
+        let frame_size = self.source_frame_size();
+        let sample_size = self.fmt.sample_format.size() as usize;
+
+        let data_read = self.data_read;
+        self.data_read = 0;
+        let adpcm = self.adpcm.clone();
+        if let Some(adpcm) = &mut self.adpcm {
+            adpcm.clear();
+        }
+        self.file
+            .seek_from_start(self.data_start)
+            .map_err(Error::PlatformError)?;
+
+        let mut buf = [0_u8; 64];
+        let chunk_len = buf.len() - buf.len() % frame_size;
+        let result = loop {
+            let len = match self.read_source(&mut buf[..chunk_len]) {
+                Ok(0) => break Ok(()),
+                Ok(len) => len,
+                Err(e) => break Err(e),
+            };
+            for bytes in buf[..len].chunks_exact_mut(frame_size) {
+                if self.sample_endian == Endian::Big {
+                    bytes
+                        .chunks_exact_mut(sample_size)
+                        .for_each(<[u8]>::reverse);
+                }
+                f(bytes);
+            }
+        };
+
+        self.data_read = data_read;
+        self.adpcm = adpcm;
+        self.file
+            .seek_from_start(self.file_offset(data_read))
+            .map_err(Error::PlatformError)?;
+        result
+    }
+
+    /// copy the stored little endian bytes of the next frame into `out` without consuming it,
+    /// the next read still starts at that frame
+    ///
+    /// returns the number of bytes copied, 0 at EOF. The file has to seek back afterwards,
+    /// panics if `out` is shorter than a stored frame
+    pub fn peek_frame_bytes(&mut self, out: &mut [u8]) -> Result<usize, Error> {
+        let frame_size = self.source_frame_size();
+        let sample_size = self.fmt.sample_format.size() as usize;
+        let frame = &mut out[..frame_size];
+
+        let data_read = self.data_read;
+        let adpcm = self.adpcm.clone();
+        let mut read = 0;
+        let result = loop {
+            match self.read_source(&mut frame[read..]) {
+                Ok(0) => break Ok(read),
+                Ok(len) if read + len == frame_size => break Ok(frame_size),
+                Ok(len) => read += len,
+                Err(e) => break Err(e),
+            }
+        };
+        if self.sample_endian == Endian::Big {
+            frame[..read]
+                .chunks_exact_mut(sample_size)
+                .for_each(<[u8]>::reverse);
+        }
+
+        self.data_read = data_read;
+        self.adpcm = adpcm;
+        self.file
+            .seek_from_start(self.file_offset(data_read))
+            .map_err(Error::PlatformError)?;
+        result
+    }
+
+    /// copy the whole fmt chunk into `buf` for codecs this crate can't decode
+    ///
+    /// returns the number of bytes copied, the read position is left unchanged
+    pub fn raw_fmt(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        // the pad byte after an odd length chunk isn't part of it
+        let fmt_len = self.fmt_chunk.data_len();
+        let buf = buf.get_mut(..fmt_len).ok_or(Error::ChunkSizeIncorrect)?;
+
+        self.file
+            .seek_from_start(self.fmt_chunk.start)
+            .map_err(Error::PlatformError)?;
+        let copied = read_full(&mut self.file, buf).map_err(Error::PlatformError)?;
+        self.file
+            .seek_from_start(self.file_offset(self.data_read))
+            .map_err(Error::PlatformError)?;
+
+        Ok(copied)
+    }
+
+    /// write a canonical header for the parsed format into `out`, the RIFF header, a fmt
+    /// chunk and the data chunk header, so it can be patched and written back
+    ///
+    /// the fmt chunk is WAVE_FORMAT_EXTENSIBLE if the file's was, and the data chunk covers
+    /// the stored samples only, other chunks aren't written. Returns the header length, or
+    /// [`Error::ChunkSizeIncorrect`] if `out` is too short. MS ADPCM and signed 8 bit
+    /// samples can't be described and return [`Error::UnsupportedAudioFormat`]
+    pub fn write_header(&self, out: &mut [u8]) -> Result<usize, Error> {
+        let tag: u16 = match (self.fmt.audio_format, self.fmt.sample_format) {
+            (AudioFormat::MsAdpcm, _) | (_, SampleFormat::I8) => {
+                return Err(Error::UnsupportedAudioFormat);
+            }
+            (AudioFormat::Pcm, _) => 1,
+            (AudioFormat::IeeeFloat, _) => 3,
+        };
+        let fmt_len: u32 = match self.fmt.channel_mask {
+            Some(_) => 40,
+            None => 16,
+        };
+        let header_len = 12 + 8 + fmt_len as usize + 8;
+        let out = out.get_mut(..header_len).ok_or(Error::ChunkSizeIncorrect)?;
+
+        let data_len = self.audio_bytes() as u32;
+        let block_align = frame_size(self.fmt.sample_format, self.fmt.channels) as u16;
+        let bits = self.fmt.sample_format.size() as u16 * 8;
+        let riff_len = 4 + 8 + fmt_len + 8 + data_len + data_len % 2;
+
+        out[0..4].copy_from_slice(b"RIFF");
+        out[4..8].copy_from_slice(&riff_len.to_le_bytes());
+        out[8..12].copy_from_slice(b"WAVE");
+        out[12..16].copy_from_slice(b"fmt ");
+        out[16..20].copy_from_slice(&fmt_len.to_le_bytes());
+        let format_tag = match self.fmt.channel_mask {
+            Some(_) => WAVE_FORMAT_EXTENSIBLE,
+            None => tag,
+        };
+        out[20..22].copy_from_slice(&format_tag.to_le_bytes());
+        out[22..24].copy_from_slice(&self.fmt.channels.count().to_le_bytes());
+        out[24..28].copy_from_slice(&(self.fmt.sample_rate as u32).to_le_bytes());
+        let byte_rate = self.fmt.sample_rate as u32 * block_align as u32;
+        out[28..32].copy_from_slice(&byte_rate.to_le_bytes());
+        out[32..34].copy_from_slice(&block_align.to_le_bytes());
+        out[34..36].copy_from_slice(&bits.to_le_bytes());
+        if let Some(channel_mask) = self.fmt.channel_mask {
+            out[36..38].copy_from_slice(&22_u16.to_le_bytes());
+            out[38..40].copy_from_slice(&self.fmt.valid_bits.unwrap_or(bits).to_le_bytes());
+            out[40..44].copy_from_slice(&channel_mask.to_le_bytes());
+            // the sub format GUID starts with the real format tag
+            out[44..46].copy_from_slice(&tag.to_le_bytes());
+            out[46..60].copy_from_slice(&[
+                0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+            ]);
+        }
+        out[header_len - 8..header_len - 4].copy_from_slice(b"data");
+        out[header_len - 4..].copy_from_slice(&data_len.to_le_bytes());
+        Ok(header_len)
+    }
+
+    /// read the ID3v1 tag some encoders append after the RIFF data
+    ///
+    /// returns `None` if the file doesn't end in a tag, the read position is left unchanged
+    pub fn id3v1(&mut self) -> Result<Option<Id3v1>, Error> {
+        let file_len = self.file.length();
+        if file_len < ID3V1_LEN as u64 {
+            return Ok(None);
+        }
+
+        let mut tag = [0_u8; ID3V1_LEN];
+        let read = read_at(&mut self.file, file_len - ID3V1_LEN as u64, &mut tag)?;
+        self.file
+            .seek_from_start(self.file_offset(self.data_read))
+            .map_err(Error::PlatformError)?;
+
+        Ok(match read {
+            ID3V1_LEN => parse_id3v1(&tag),
+            _ => None,
+        })
+    }
+
+    /// read bytes from the file's current position, ignoring the data chunk bounds
+    ///
+    /// this bypasses every audio safety check: the bytes aren't converted or frame aligned and
+    /// the audio read position isn't updated. Call [`Wav::sync_position`] before reading
+    /// samples again
+    pub fn read_raw(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        read_full(&mut self.file, buf).map_err(Error::PlatformError)
+    }
+
+    /// move the file to `offset` bytes from its start, for use with [`Wav::read_raw`]
+    ///
+    /// like [`Wav::read_raw`] the audio read position isn't updated
+    pub fn seek_raw(&mut self, offset: u64) -> Result<(), Error> {
+        self.file
+            .seek_from_start(offset)
+            .map_err(Error::PlatformError)
+    }
+
+    /// bytes in one frame of the stored samples, before any output conversion
+    fn source_frame_size(&self) -> usize {
+        frame_size(self.fmt.sample_format, self.fmt.channels)
+    }
+
+    /// read samples converted to the output format and endianness
+    fn read_converted(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let source = self.fmt.sample_format;
+        let output = self.output_format.unwrap_or(source);
+        let swap = self.sample_endian == Endian::Big;
+        let padding = self.padding_mask();
+        if output == source && !swap && self.gain.is_none() && padding.is_none() {
+            return self.read_source(buf);
+        }
+
+        let (source_size, output_size) = (source.size() as usize, output.size() as usize);
+        let channels = self.channels().count() as usize;
+
+        // Only convert whole frames so interleaved channels stay aligned
+        let mut samples = buf.len() / output_size;
+        samples -= samples % channels;
+
+        let mut scratch = [0_u8; 64];
+        let chunk_len = scratch.len() - scratch.len() % (source_size * channels);
+        let mut converted = 0;
+        while converted < samples {
+            let want = ((samples - converted) * source_size).min(chunk_len);
+            let len = self.read_source(&mut scratch[..want])?;
+            if len == 0 {
+                break;
+            }
+            for bytes in scratch[..len].chunks_exact_mut(source_size) {
+                if swap {
+                    bytes.reverse();
+                }
+                let mut sample = source.to_i32(bytes);
+                if let Some(padding) = padding {
+                    sample &= !padding;
+                }
+                if let Some(gain) = self.gain {
+                    sample = convert::scale(sample, gain);
+                }
+                if output_size < source_size
+                    && let Some(dither) = &mut self.dither
+                {
+                    sample = dither.apply(sample, output);
+                }
+                let out = &mut buf[converted * output_size..];
+                output.write_i32(sample, out);
+                converted += 1;
+            }
+        }
+        Ok(converted * output_size)
+    }
+
+    /// bits of the widened 32 bit sample below the valid bits, `None` if there are none
+    fn padding_mask(&self) -> Option<i32> {
+        let container = self.fmt.sample_format.size() as u16 * 8;
+        match self.fmt.valid_bits {
+            _ if matches!(
+                self.fmt.sample_format,
+                SampleFormat::F32 | SampleFormat::F64
+            ) =>
+            {
+                None
+            }
+            Some(valid_bits) if valid_bits < container => {
+                Some(((1_i64 << (32 - valid_bits)) - 1) as i32)
+            }
+            _ => None,
+        }
+    }
+
+    /// total bytes of samples across every data chunk
+    fn data_bytes(&self) -> u64 {
+        self.segments.iter().map(Segment::len).sum()
+    }
+
+    /// bytes of stored samples `encoded` bytes of data decode to
+    fn decoded_len(&self, encoded: u64) -> u64 {
+        match &self.adpcm {
+            Some(adpcm) => adpcm.decoded_samples(encoded) * 2,
+            None => encoded,
+        }
+    }
+
+    /// bytes of stored samples handed out so far
+    fn source_read(&self) -> u64 {
+        match &self.adpcm {
+            Some(adpcm) => self.decoded_len(self.data_read) - adpcm.pending() as u64 * 2,
+            None => self.data_read,
+        }
+    }
+
+    /// total bytes of stored samples once decoded
+    fn source_bytes(&self) -> u64 {
+        self.decoded_len(self.data_bytes())
+    }
+
+    /// file offset of the sample `data_offset` bytes into the data
+    fn file_offset(&self, data_offset: u64) -> u64 {
+        self.locate(data_offset).0
+    }
+
+    /// find the file offset `data_offset` bytes into the data, the bytes left until the
+    /// samples or silence it's in end and whether it's in silence
+    fn locate(&self, mut data_offset: u64) -> (u64, u64, bool) {
+        for segment in &self.segments {
+            let stored = segment.end - segment.start;
+            if data_offset < stored {
+                return (segment.start + data_offset, stored - data_offset, false);
+            }
+            if data_offset < segment.len() {
+                return (segment.end, segment.len() - data_offset, true);
+            }
+            data_offset -= segment.len();
+        }
+        (
+            self.segments.last().map_or(self.data_start, |s| s.end),
+            0,
+            false,
+        )
+    }
+
+    /// count a read of `len` bytes into a buffer `buf_len` bytes long
+    #[cfg(feature = "diagnostics")]
+    fn count_read(&mut self, buf_len: usize, len: usize) {
+        let requested = buf_len - buf_len % self.frame_size();
+        self.diagnostics.total_reads = self.diagnostics.total_reads.saturating_add(1);
+        if len < requested && !self.is_eof() {
+            self.diagnostics.short_reads = self.diagnostics.short_reads.saturating_add(1);
+        }
+    }
+
+    /// read samples in the source sample format, decoding them if the data is compressed
+    fn read_source(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let Some(mut adpcm) = self.adpcm.take() else {
+            return self.read_data(buf);
+        };
+        let result = self.read_adpcm(&mut adpcm, buf);
+        self.adpcm = Some(adpcm);
+        result
+    }
+
+    /// decode whole frames of MS ADPCM into 16 bit samples
+    fn read_adpcm(&mut self, adpcm: &mut MsAdpcm, buf: &mut [u8]) -> Result<usize, Error> {
+        let channels = self.channels().count() as usize;
+        let mut samples = buf.len() / 2;
+        samples -= samples % channels;
+
+        let mut decoded = 0;
+        let mut put = |sample: i16, decoded: &mut usize| {
+            buf[*decoded * 2..*decoded * 2 + 2].copy_from_slice(&sample.to_le_bytes());
+            *decoded += 1;
+        };
+        while decoded < samples {
+            if let Some(sample) = adpcm.pop() {
+                put(sample, &mut decoded);
+                continue;
+            }
+
+            let block_offset = (self.data_read % adpcm.block_align() as u64) as usize;
+            if block_offset == 0 {
+                let mut header = [0_u8; 14];
+                let header = &mut header[..adpcm.header_len()];
+                if self.read_data_unaligned(header)? < header.len() {
+                    break;
+                }
+                adpcm.start_block(header)?;
+                continue;
+            }
+
+            // every byte holds two samples, only read as many as are needed
+            let mut bytes = [0_u8; 32];
+            let want = (samples - decoded)
+                .div_ceil(2)
+                .min(adpcm.block_align() - block_offset)
+                .min(bytes.len());
+            let len = self.read_data_unaligned(&mut bytes[..want])?;
+            if len == 0 {
+                break;
+            }
+            for byte in &bytes[..len] {
+                adpcm.decode_byte(*byte);
+                while decoded < samples
+                    && let Some(sample) = adpcm.pop()
+                {
+                    put(sample, &mut decoded);
+                }
+            }
+        }
+        Ok(decoded * 2)
+    }
+
+    /// read raw data chunk bytes in the source sample format
+    fn read_data(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        // How many bytes are left to read from the data chunks
+        let data_left = self.data_bytes().saturating_sub(self.data_read);
+
+        // Limit the read to the amount of data left
+        let mut to_read = (buf.len() as u64).min(data_left) as usize;
+
+        // Only hand out whole frames so interleaved channels stay aligned
+        let frame_size = self.source_frame_size();
+        to_read -= to_read % frame_size;
+        let (read, error) = self.fill_data(&mut buf[..to_read]);
+
+        // a short or failed read can stop part way through a frame, step back to the last
+        // whole frame so the next read starts on the right channel
+        let whole = read - read % frame_size;
+        if whole < read {
+            self.data_read -= (read - whole) as u64;
+            self.file
+                .seek_from_start(self.file_offset(self.data_read))
+                .map_err(Error::PlatformError)?;
+        }
+        match error {
+            Some(e) if whole == 0 => Err(e),
+            // report the frames that were read, the error will resurface on the next read
+            _ => Ok(whole),
+        }
+    }
+
+    /// read raw data chunk bytes across data chunks and silence, without frame alignment
+    fn read_data_unaligned(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        match self.fill_data(buf) {
+            (0, Some(e)) => Err(e),
+            // report what was read, the error will resurface on the next read
+            (read, _) => Ok(read),
+        }
+    }
+
+    /// read raw data chunk bytes across data chunks and silence, returning how many were read
+    /// and the error that stopped the read early
+    fn fill_data(&mut self, buf: &mut [u8]) -> (usize, Option<Error>) {
+        // If no more data left to read
+        let data_left = self.data_bytes().saturating_sub(self.data_read);
+        if data_left == 0 {
+            return (0, None);
+        }
+        let to_read = (buf.len() as u64).min(data_left) as usize;
+
+        let mut read = 0;
+        while read < to_read {
+            let (_, segment_left, silent) = self.locate(self.data_read);
+            let want = ((to_read - read) as u64).min(segment_left) as usize;
+            let (len, error) = if silent {
+                let silence = match self.fmt.sample_format {
+                    SampleFormat::U8 => 0x80,
+                    _ => 0,
+                };
+                buf[read..read + want].fill(silence);
+                (want, None)
+            } else {
+                self.read_file(&mut buf[read..read + want])
+            };
+            read += len;
+            self.data_read += len as u64;
+            if let Some(e) = error {
+                // a failed read can leave the file anywhere, go back to the end of what was read
+                let seek = self.file.seek_from_start(self.file_offset(self.data_read));
+                return (read, Some(seek.err().map_or(e, Error::PlatformError)));
+            }
+            if len < want {
+                break;
+            }
+            if len as u64 == segment_left && self.data_read < self.data_bytes() {
+                // move on to the next data chunk
+                if let Err(e) = self.file.seek_from_start(self.file_offset(self.data_read)) {
+                    return (read, Some(Error::PlatformError(e)));
+                }
+            }
+        }
+        (read, None)
+    }
+
+    /// read stored samples at the current position until `buf` is full or EOF, trying again
+    /// from where the read stopped if the backend fails with [`PlatformFileError::Io`]
+    ///
+    /// returns how many bytes were read and the error that stopped the read early
+    fn read_file(&mut self, buf: &mut [u8]) -> (usize, Option<Error>) {
+        let mut retries = self.retries;
+        let mut read = 0;
+        while read < buf.len() {
+            match self.file.read(&mut buf[read..]) {
+                Ok(0) | Err(PlatformFileError::EOF) => break,
+                Ok(len) => read += len,
+                Err(PlatformFileError::Io) if retries > 0 => {
+                    retries -= 1;
+                    let offset = self.file_offset(self.data_read + read as u64);
+                    if let Err(e) = self.file.seek_from_start(offset) {
+                        return (read, Some(Error::PlatformError(e)));
+                    }
+                }
+                Err(e) => return (read, Some(Error::PlatformError(e))),
+            }
+        }
+        (read, None)
+    }
+}
+
+impl<File: PlatformFile> AudioFile<File> for Wav<File> {
+    type Error = Error;
+
+    /// only whole frames are read, a `buf` shorter than one frame reads nothing and returns 0
+    ///
+    /// files parsed in [`ParseMode::Strict`] return [`Error::MisalignedPosition`] if the
+    /// position was moved part way through a frame, otherwise the frame is read from where
+    /// it is
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if self.mode == ParseMode::Strict
+            && !self
+                .source_read()
+                .is_multiple_of(self.source_frame_size() as u64)
+        {
+            return Err(Error::MisalignedPosition);
+        }
+        // a header only file has nothing to read, don't touch the file
+        let len = match self.is_eof() {
+            true => 0,
+            false => self.read_converted(buf)?,
+        };
+        #[cfg(feature = "diagnostics")]
+        self.count_read(buf.len(), len);
+        if len > 0 || !self.is_eof() {
+            return Ok(len);
+        }
+
+        match self.eof_mode {
+            EofMode::Stop => Ok(0),
+            // an empty data chunk would loop forever
+            EofMode::Loop if self.data_bytes() == 0 => Ok(0),
+            EofMode::Loop => {
+                self.restart()?;
+                self.read_converted(buf)
+            }
+            EofMode::Silence => {
+                let format = self.sample_format();
+                let sample_size = format.size() as usize;
+                let mut samples = buf.len() / sample_size;
+                let channels = self.channels().count() as usize;
+                samples -= samples % channels;
+                for sample in buf[..samples * sample_size].chunks_exact_mut(sample_size) {
+                    format.write_i32(0, sample);
+                }
+                Ok(samples * sample_size)
+            }
+        }
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.fmt.sample_rate
+    }
+
+    fn channels(&self) -> Channels {
+        self.fmt.channels
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.output_format.unwrap_or(self.fmt.sample_format)
+    }
+
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Self::Error> {
+        let byte_offset = sample_offset * self.fmt.sample_format.size() as i64;
+        let target = self.source_read() as i64 + byte_offset;
+        // MS ADPCM can only be decoded from the start of a block
+        let encoded = match &self.adpcm {
+            Some(adpcm) => adpcm.block_start(target.max(0) as u64 / 2),
+            None => target.max(0) as u64,
+        };
+        let result = if target < 0 || target > self.source_bytes() as i64 {
+            Err(Error::PositionOutOfBounds)
+        } else {
+            self.file
+                .seek_from_start(self.file_offset(encoded))
+                .map_err(Error::PlatformError)
+        };
+
+        match result {
+            Ok(()) => {
+                self.data_read = encoded;
+                if let Some(adpcm) = &mut self.adpcm {
+                    adpcm.clear();
+                }
+                Ok(())
+            }
+            Err(e) if self.seek_policy == SeekPolicy::Panic => {
+                panic!(
+                    "failed to seek {sample_offset} samples from sample {}: {e:?}",
+                    self.played()
+                )
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn is_eof(&self) -> bool {
+        self.source_read() >= self.source_bytes()
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.file.can_seek()
+    }
+
+    fn played(&self) -> usize {
+        (self.source_read() / self.fmt.sample_format.size() as u64) as usize
+    }
+
+    fn remaining(&self) -> usize {
+        let left = self.source_bytes().saturating_sub(self.source_read());
+        (left / self.fmt.sample_format.size() as u64) as usize
+    }
+
+    fn data_len(&self) -> usize {
+        let samples = self.source_bytes() / self.fmt.sample_format.size() as u64;
+        (samples * self.sample_format().size() as u64) as usize
+    }
+
+    /// seeks past the frames, or reads and discards them if the file can't seek or the
+    /// samples are compressed
+    fn skip_frames(&mut self, frames: usize) -> Result<(), Error> {
+        let channels = self.channels().count() as usize;
+        if self.file.can_seek() && self.adpcm.is_none() {
+            let left = self.total_frames().saturating_sub(self.played() / channels);
+            return self.try_seek((frames.min(left) * channels) as i64);
+        }
+
+        let mut scratch = [0_u8; 64];
+        let chunk_len = scratch.len() - scratch.len() % self.source_frame_size();
+        let mut left = frames * self.source_frame_size();
+        while left > 0 {
+            let len = self.read_source(&mut scratch[..left.min(chunk_len)])?;
+            if len == 0 {
+                break;
+            }
+            left -= len;
+        }
+        Ok(())
+    }
+}
+
+/// Read counters for tuning buffer sizes
+#[cfg(feature = "diagnostics")]
+#[derive(Copy, Clone, Debug, Default)]
+struct Diagnostics {
+    short_reads: u32,
+    total_reads: u32,
+}
+
+impl<File: PlatformFile> Wav<OffsetFile<File>> {
+    /// parse a file with stray bytes before its RIFF header, such as from a broken exporter
+    ///
+    /// the first [`MAX_SCAN_LEN`] bytes are searched for the header and parsing starts there,
+    /// otherwise [`Error::NoRiffChunkFound`] is returned. The file must be at its start and
+    /// able to seek
+    pub fn new_scan(mut file: File) -> Result<Self, Error> {
+        if !file.can_seek() {
+            return Err(Error::SeekUnsupported);
+        }
+
+        let mut buf = [0_u8; 64];
+        // keep the last bytes of each window in case the tag straddles two
+        let keep = 3;
+        let mut filled = 0;
+        let mut window_start = 0;
+        let base = loop {
+            let len = read_full(&mut file, &mut buf[filled..]).map_err(Error::PlatformError)?;
+            let end = filled + len;
+            if let Some(i) = buf[..end].windows(4).position(|tag| tag == b"RIFF") {
+                break window_start + i as u64;
+            }
+            if len == 0 || window_start + end as u64 >= MAX_SCAN_LEN {
+                return Err(Error::NoRiffChunkFound);
+            }
+            let kept = end.min(keep);
+            buf.copy_within(end - kept..end, 0);
+            window_start += (end - kept) as u64;
+            filled = kept;
+        };
+
+        let file = OffsetFile::new(file, base).map_err(Error::PlatformError)?;
+        Self::new(file)
+    }
+}
+
+/// Reads the data of a single chunk, see [`Wav::chunk_reader`]
+pub struct ChunkReader<'a, File: PlatformFile> {
+    wav: &'a mut Wav<File>,
+    pos: u64,
+    end: u64,
+}
+
+impl<File: PlatformFile> ChunkReader<'_, File> {
+    /// read chunk data, returns 0 once the end of the chunk is reached
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let to_read = (buf.len() as u64).min(self.remaining()) as usize;
+        let read =
+            read_full(&mut self.wav.file, &mut buf[..to_read]).map_err(Error::PlatformError)?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+
+    /// number of chunk bytes left to read
+    pub fn remaining(&self) -> u64 {
+        self.end - self.pos
+    }
+}
+
+impl<File: PlatformFile> Drop for ChunkReader<'_, File> {
+    fn drop(&mut self) {
+        // a failed seek resurfaces on the next audio read
+        let _ = self
+            .wav
+            .file
+            .seek_from_start(self.wav.file_offset(self.wav.data_read));
+    }
+}
+
+/// parses the file in the first pass to find out where each chunk is located
+///
+/// the RIFF size is often wrong so it isn't used to bound the scan. [`ParseMode::Lenient`]
+/// also finds the chunk after an odd sized one whose pad byte is missing
+fn parse_chunks<File: PlatformFile, const MAX_CHUNKS: usize>(
+    buf: &mut [u8],
+    file: &mut File,
+    chunks: &mut Vec<Chunk, MAX_CHUNKS>,
+    file_offset: u64,
+    mode: ParseMode,
+) -> Result<(), Error> {
+    // the previous chunk ran to (or past) the end of the file
+    if file_offset >= file.length() {
+        return Ok(());
+    }
+
+    file.seek_from_start(file_offset)
+        .map_err(Error::PlatformError)?;
+    let read_len = read_full(file, buf).map_err(Error::PlatformError)?;
+
+    if read_len == 0 {
+        return Ok(()); // EOF
+    }
+
+    let mut index = 0;
+
+    while index + 8 <= read_len {
+        let mut chunk = parse_chunk(
+            &buf[index..index + 8]
+                .try_into()
+                .map_err(|_| Error::ChunkSizeIncorrect)?,
+            file_offset + index as u64,
+        );
+        if chunk.chunk == ChunkTag::Fmt && chunk.end - chunk.start == 18 {
+            let mut after_base = [0_u8; 4];
+            if index + 28 <= read_len {
+                after_base.copy_from_slice(&buf[index + 24..index + 28]);
+            } else {
+                read_at(file, chunk.start + 16, &mut after_base)?;
+            }
+            trim_missing_cb_size(&mut chunk, &after_base);
+        }
+        if mode == ParseMode::Lenient && chunk.padded {
+            let mut after_data = [0_u8; 5];
+            let data_end = index + 8 + chunk.data_len();
+            if data_end + 5 <= read_len {
+                after_data.copy_from_slice(&buf[data_end..data_end + 5]);
+            } else {
+                read_at(file, chunk.start + chunk.data_len() as u64, &mut after_data)?;
+            }
+            trim_missing_pad(&mut chunk, &after_data);
+        }
+        chunks.push(chunk).map_err(|_| Error::ExceededMaxChunks)?;
+
+        let last_chunk = chunks.last().unwrap();
+        let chunk_len = last_chunk.end - last_chunk.start + 8;
+
+        if index as u64 + chunk_len <= read_len as u64 {
+            index += chunk_len as usize;
+        } else {
+            return parse_chunks(buf, file, chunks, chunks.last().unwrap().end, mode);
+        }
+    }
+
+    // fewer bytes than a chunk header are left at the end of the file
+    if read_len < buf.len() {
+        return Ok(());
+    }
+    // the next chunk header straddles the end of the buffer
+    parse_chunks(buf, file, chunks, file_offset + index as u64, mode)
+}
+
+/// drop the pad byte of an odd sized chunk if the next chunk starts right after its data
+///
+/// `after_data` is the 5 bytes after the data, the next tag either starts at the first byte
+/// or after the pad byte
+fn trim_missing_pad(chunk: &mut Chunk, after_data: &[u8; 5]) {
+    let unpadded = ChunkTag::from_bytes_ignore_case(after_data[..4].try_into().unwrap());
+    let padded = ChunkTag::from_bytes_ignore_case(after_data[1..].try_into().unwrap());
+    if matches!(padded, ChunkTag::Unknown(_)) && !matches!(unpadded, ChunkTag::Unknown(_)) {
+        chunk.end -= 1;
+        chunk.padded = false;
+    }
+}
+
+/// shorten an 18 byte fmt chunk whose cbSize is missing, returns true if it was
+///
+/// some encoders declare 18 bytes but only write the base 16, so `after_base`, the 4 bytes
+/// after the base fields, holds the next chunk's tag instead of cbSize
+fn trim_missing_cb_size(chunk: &mut Chunk, after_base: &[u8; 4]) -> bool {
+    let next_tag = match ChunkTag::from_bytes(after_base) {
+        ChunkTag::Unknown(tag) => tag == *b"fact",
+        _ => true,
+    };
+    if chunk.end - chunk.start != 18 || after_base[..2] == [0, 0] || !next_tag {
+        return false;
+    }
+    chunk.end -= 2;
+    true
+}
+
+/// collect the `data` and `slnt` chunks of a `wavl` list, other list types are ignored
+fn parse_wavl<File: PlatformFile>(
+    file: &mut File,
+    chunk: &Chunk,
+    data_chunks: &mut Vec<Chunk, MAX_DATA_CHUNKS>,
+) -> Result<(), Error> {
+    let mut list_type = [0_u8; 4];
+    read_at(file, chunk.start, &mut list_type)?;
+    if list_type != *b"wavl" {
+        return Ok(());
+    }
+
+    let mut offset = chunk.start + 4;
+    while offset + 8 <= chunk.end {
+        let mut header = [0_u8; 8];
+        if read_at(file, offset, &mut header)? < header.len() {
+            break;
+        }
+        let sub_chunk = parse_chunk(&header, offset);
+        if matches!(sub_chunk.chunk, ChunkTag::Data | ChunkTag::Slnt) {
+            data_chunks
+                .push(sub_chunk)
+                .map_err(|_| Error::ExceededMaxChunks)?;
+        }
+        offset = sub_chunk.end;
+    }
+    Ok(())
+}
+
+/// seek to `offset` in the file then read until `buf` is full or EOF
+fn read_at<File: PlatformFile>(
+    file: &mut File,
+    offset: u64,
+    buf: &mut [u8],
+) -> Result<usize, Error> {
+    file.seek_from_start(offset).map_err(Error::PlatformError)?;
+    read_full(file, buf).map_err(Error::PlatformError)
+}
+
+/// feed `bytes` into a running reflected CRC-32 with the IEEE polynomial
+fn crc32_update(mut crc: u32, bytes: &[u8]) -> u32 {
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xedb8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    crc
+}
+
+/// convert a file offset to an index into bytes held in memory
+fn in_memory(offset: u64) -> Result<usize, Error> {
+    usize::try_from(offset).map_err(|_| Error::NeedMoreData)
+}
+
+/// skip `len` bytes by reading them, for files that can't seek
+fn skip<File: PlatformFile>(file: &mut File, mut len: u64) -> Result<(), Error> {
+    let mut scratch = [0_u8; 64];
+    while len > 0 {
+        let to_read = len.min(scratch.len() as u64) as usize;
+        match read_full(file, &mut scratch[..to_read]).map_err(Error::PlatformError)? {
+            0 => break,
+            read => len -= read as u64,
+        }
+    }
+    Ok(())
+}
+
+/// reads until `buf` is full or EOF, as backing files may return short reads
+fn read_full<File: PlatformFile>(
+    file: &mut File,
+    buf: &mut [u8],
+) -> Result<usize, PlatformFileError> {
+    let mut read = 0;
+    while read < buf.len() {
+        match file.read(&mut buf[read..]) {
+            Ok(0) | Err(PlatformFileError::EOF) => break,
+            Ok(len) => read += len,
+            Err(e) if read == 0 => return Err(e),
+            // report what was read, the error will resurface on the next read
+            Err(_) => break,
+        }
+    }
+    Ok(read)
+}
+
+/// base 10 logarithm for positive `x`, core has no float math without std
+///
+/// splits off the exponent and approximates the natural log of the mantissa with a series,
+/// accurate to about 1e-5
+fn log10(x: f32) -> f32 {
+    let bits = x.to_bits();
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127;
+    let mantissa = f32::from_bits((bits & 0x007f_ffff) | 0x3f80_0000);
+
+    // ln(m) = 2 * atanh((m - 1) / (m + 1))
+    let t = (mantissa - 1.0) / (mantissa + 1.0);
+    let t2 = t * t;
+    let ln_mantissa = 2.0 * t * (1.0 + t2 * (1.0 / 3.0 + t2 * (1.0 / 5.0 + t2 / 7.0)));
+    (exponent as f32 * core::f32::consts::LN_2 + ln_mantissa) * core::f32::consts::LOG10_E
+}
+
+fn parse_chunk(bytes: &[u8; 8], index: u64) -> Chunk {
+    let tag = ChunkTag::from_bytes(&bytes[..4].try_into().unwrap());
+    let chunk_len = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as u64;
+
+    // padding if chunk_len is odd (RIFF word alignment)
+    let padded = !chunk_len.is_multiple_of(2);
+
+    Chunk {
+        chunk: tag,
+        start: index + 8,
+        end: index + 8 + chunk_len + padded as u64,
+        padded,
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum ChunkTag {
+    Riff,
+    Wave,
+    Fmt,
+    Data,
+    Cue,
+    List,
+    Peak,
+    Slnt,
+    Disp,
+    Bext,
+    Unknown([u8; 4]),
+}
+
+impl ChunkTag {
+    fn from_bytes(bytes: &[u8; 4]) -> Self {
+        match bytes {
+            [b'R', b'I', b'F', b'F'] => Self::Riff,
+            [b'W', b'A', b'V', b'E'] => Self::Wave,
+            [b'd', b'a', b't', b'a'] => Self::Data,
+            [b'f', b'm', b't', b' '] => Self::Fmt,
+            [b'c', b'u', b'e', b' '] => Self::Cue,
+            [b'L', b'I', b'S', b'T'] => Self::List,
+            [b'P', b'E', b'A', b'K'] => Self::Peak,
+            [b's', b'l', b'n', b't'] => Self::Slnt,
+            [b'D', b'I', b'S', b'P'] => Self::Disp,
+            [b'b', b'e', b'x', b't'] => Self::Bext,
+            _ => Self::Unknown(*bytes),
+        }
+    }
+
+    /// match tags such as `FMT ` or `Data` too, every known tag is in a single case
+    fn from_bytes_ignore_case(bytes: &[u8; 4]) -> Self {
+        match Self::from_bytes(&bytes.map(|byte| byte.to_ascii_lowercase())) {
+            Self::Unknown(_) => Self::from_bytes(&bytes.map(|byte| byte.to_ascii_uppercase())),
+            tag => tag,
+        }
+    }
+}
+
+/// Samples stored in one data chunk as file offsets, or silence declared by a `slnt` chunk
+/// that isn't stored
+#[derive(Copy, Clone, Debug)]
+struct Segment {
+    start: u64,
+    end: u64,
+    /// bytes of silence after the stored samples
+    silence: u64,
+}
+
+impl Segment {
+    fn len(&self) -> u64 {
+        self.end - self.start + self.silence
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Chunk {
+    /// start of chunk data after chunk tag and len
+    pub start: u64,
+    /// chunk tag/id
+    pub chunk: ChunkTag,
+    /// end of the chunk, including the pad byte after an odd length
+    pub end: u64,
+    /// whether `end` includes a pad byte
+    pub padded: bool,
+}
+
+impl Chunk {
+    /// length of the chunk data as declared in its header, without the pad byte
+    pub fn data_len(&self) -> usize {
+        (self.end - self.start - self.padded as u64) as usize
+    }
+}
+
+/// Speaker positions of the channels in a WAVE_FORMAT_EXTENSIBLE file
+///
+/// Channels are interleaved in the order of the set bits, lowest first
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct SpeakerLayout(u32);
+
+impl SpeakerLayout {
+    pub const FRONT_LEFT: Self = Self(0x1);
+    pub const FRONT_RIGHT: Self = Self(0x2);
+    pub const FRONT_CENTER: Self = Self(0x4);
+    pub const LOW_FREQUENCY: Self = Self(0x8);
+    pub const BACK_LEFT: Self = Self(0x10);
+    pub const BACK_RIGHT: Self = Self(0x20);
+    pub const FRONT_LEFT_OF_CENTER: Self = Self(0x40);
+    pub const FRONT_RIGHT_OF_CENTER: Self = Self(0x80);
+    pub const BACK_CENTER: Self = Self(0x100);
+    pub const SIDE_LEFT: Self = Self(0x200);
+    pub const SIDE_RIGHT: Self = Self(0x400);
+    pub const TOP_CENTER: Self = Self(0x800);
+    pub const TOP_FRONT_LEFT: Self = Self(0x1000);
+    pub const TOP_FRONT_CENTER: Self = Self(0x2000);
+    pub const TOP_FRONT_RIGHT: Self = Self(0x4000);
+    pub const TOP_BACK_LEFT: Self = Self(0x8000);
+    pub const TOP_BACK_CENTER: Self = Self(0x10000);
+    pub const TOP_BACK_RIGHT: Self = Self(0x20000);
+
+    /// Front left and front right
+    pub const STEREO: Self = Self(0x3);
+    /// Front left, front right, front center, LFE, back left and back right
+    pub const SURROUND_5_1: Self = Self(0x3f);
+
+    /// create a layout from a raw dwChannelMask
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// get the raw dwChannelMask
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// check if every speaker in `other` is part of this layout
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// number of speakers in the layout
+    pub const fn count(&self) -> u32 {
+        self.0.count_ones()
+    }
+}
+
+impl core::ops::BitOr for SpeakerLayout {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+#[allow(dead_code)]
+struct Fmt {
+    audio_format: AudioFormat,
+    sample_rate: u16,
+    channels: Channels,
+    sample_format: SampleFormat,
+    extra: Option<ExtraFmtParam>,
+    /// speaker positions from a WAVE_FORMAT_EXTENSIBLE header
+    channel_mask: Option<u32>,
+    /// bits of each sample container that hold the sample, `None` for compressed data
+    valid_bits: Option<u16>,
+    /// block layout of MS ADPCM data
+    ms_adpcm: Option<MsAdpcmFmt>,
+    /// byte rate over block align, `None` for compressed data or a zero block align
+    derived_sample_rate: Option<u32>,
+    /// the declared sample rate matches the derived one
+    rate_consistent: bool,
+}
+
+/// Extension of a fmt chunk longer than 16 bytes
+#[allow(dead_code)]
+struct ExtraFmtParam {
+    /// `cbSize`, bytes of extension after the size field
+    param_size: u16,
+    // params: &[]
+}
+
+/// Format tag whose real format is stored in the sub format GUID of the fmt extension
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xfffe;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+enum AudioFormat {
+    Pcm,
+    MsAdpcm,
+    IeeeFloat,
+}
+
+impl AudioFormat {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let format = u16::from_le_bytes(bytes.try_into().map_err(|_| Error::ChunkSizeIncorrect)?);
+        match format {
+            1 => Ok(Self::Pcm),
+            2 => Ok(Self::MsAdpcm),
+            3 => Ok(Self::IeeeFloat),
+            _ => Err(Error::UnsupportedAudioFormat),
+        }
+    }
+}
+
+/// read and parse the fmt chunk, only its own bytes are read so a short chunk can't pick up
+/// the start of the next one
+fn read_fmt<File: PlatformFile>(
+    file: &mut File,
+    chunk: &Chunk,
+    mode: ParseMode,
+) -> Result<Fmt, Error> {
+    let chunk_len = chunk.data_len() as u64;
+    if chunk_len < 16 {
+        return Err(Error::FmtChunkError);
+    }
+    let mut buf = [0_u8; MAX_FMT_LEN];
+    let len = chunk_len.min(buf.len() as u64) as usize;
+    if read_at(file, chunk.start, &mut buf[..len])? < 16 {
+        return Err(Error::FmtChunkError);
+    }
+    parse_fmt(&buf, chunk_len, mode)
+}
+
+/// parse the fields of a fmt chunk `chunk_len` bytes long, `buf` holds at least its first bytes
+///
+/// the byte rate is only checked in [`ParseMode::Strict`], otherwise it's ignored and rates
+/// are derived from the sample rate
+fn parse_fmt(buf: &[u8], chunk_len: u64, mode: ParseMode) -> Result<Fmt, Error> {
+    // whatever follows the chunk in `buf` isn't part of it
+    let buf = &buf[..chunk_len.min(buf.len() as u64) as usize];
+    if buf.len() < 16 {
+        return Err(Error::FmtChunkError);
+    }
+    let format_tag = u16::from_le_bytes(
+        buf[0..2]
+            .try_into()
+            .map_err(|_| Error::ChunkSizeIncorrect)?,
+    );
+    let (format, channel_mask, valid_bits) = if format_tag == WAVE_FORMAT_EXTENSIBLE {
+        let valid_bits = u16::from_le_bytes(
+            buf.get(18..20)
+                .ok_or(Error::FmtChunkError)?
+                .try_into()
+                .map_err(|_| Error::ChunkSizeIncorrect)?,
+        );
+        let channel_mask = u32::from_le_bytes(
+            buf.get(20..24)
+                .ok_or(Error::FmtChunkError)?
+                .try_into()
+                .map_err(|_| Error::ChunkSizeIncorrect)?,
+        );
+        // the sub format GUID starts with the real format tag
+        let format = AudioFormat::from_bytes(buf.get(24..26).ok_or(Error::FmtChunkError)?)?;
+        (format, Some(channel_mask), Some(valid_bits))
+    } else {
+        (AudioFormat::from_bytes(&buf[0..2])?, None, None)
+    };
+
+    let num_channels = u16::from_le_bytes(
+        buf[2..4]
+            .try_into()
+            .map_err(|_| Error::ChunkSizeIncorrect)?,
+    );
+    let channels =
+        Channels::from_count(num_channels).map_err(|_| Error::UnsupportedChannelCount)?;
+
+    let sample_rate = u32::from_le_bytes(
+        buf[4..8]
+            .try_into()
+            .map_err(|_| Error::ChunkSizeIncorrect)?,
+    );
+    let byte_rate = u32::from_le_bytes(
+        buf[8..12]
+            .try_into()
+            .map_err(|_| Error::ChunkSizeIncorrect)?,
+    );
+    let block_align = u16::from_le_bytes(
+        buf[12..14]
+            .try_into()
+            .map_err(|_| Error::ChunkSizeIncorrect)?,
+    );
+    // compressed data has no fixed relation between the block align and byte rate
+    if mode == ParseMode::Strict
+        && format != AudioFormat::MsAdpcm
+        && sample_rate.checked_mul(block_align as u32) != Some(byte_rate)
+    {
+        return Err(Error::ByteRateMismatch);
+    }
+    let derived_sample_rate = match format {
+        AudioFormat::MsAdpcm => None,
+        _ => byte_rate.checked_div(block_align as u32),
+    };
+    let rate_consistent = derived_sample_rate.is_none_or(|derived| derived == sample_rate);
+    let sample_rate = sample_rate as u16;
+    let bit_depth = u16::from_le_bytes(
+        buf[14..16]
+            .try_into()
+            .map_err(|_| Error::ChunkSizeIncorrect)?,
+    );
+
+    // anything past the base 16 bytes starts with cbSize, the length of the rest. A chunk
+    // too short to hold it is treated as having none. Writers often get cbSize slightly
+    // wrong, so outside strict mode it's clamped to what the chunk holds
+    let extra = if buf.len() >= 18 {
+        let param_size = u16::from_le_bytes(buf[16..18].try_into().unwrap());
+        if 18 + param_size as u64 != chunk_len && mode == ParseMode::Strict {
+            return Err(Error::FmtChunkError);
+        }
+        let param_size = param_size.min((chunk_len - 18).min(u16::MAX as u64) as u16);
+        Some(ExtraFmtParam { param_size })
+    } else {
+        None
+    };
+
+    let encoding = match (format, bit_depth) {
+        (AudioFormat::Pcm, 8) => SampleFormat::U8,
+        (AudioFormat::Pcm, 16) => SampleFormat::I16,
+        (AudioFormat::Pcm, 24) => SampleFormat::I24,
+        (AudioFormat::Pcm, 32) => SampleFormat::I32,
+        // decoded to 16 bit samples
+        (AudioFormat::MsAdpcm, 4) => SampleFormat::I16,
+        (AudioFormat::IeeeFloat, 32) => SampleFormat::F32,
+        (AudioFormat::IeeeFloat, 64) => SampleFormat::F64,
+        // integer samples are at most 32 bit and floats are either 32 or 64 bit
+        (AudioFormat::Pcm, 33..) | (AudioFormat::IeeeFloat, ..=31 | 33..=63 | 65..) => {
+            return Err(Error::FormatDepthMismatch);
+        }
+        _ => return Err(Error::UnknownEncoding),
+    };
+
+    // the extension after cbSize holds the block size and predictor coefficients
+    let ms_adpcm = match format {
+        AudioFormat::MsAdpcm if channel_mask.is_some() => {
+            return Err(Error::UnsupportedAudioFormat);
+        }
+        AudioFormat::MsAdpcm if extra.is_none() => return Err(Error::FmtChunkError),
+        AudioFormat::MsAdpcm => Some(parse_ms_adpcm(&buf[18..], num_channels, block_align)?),
+        _ => None,
+    };
+
+    // samples are left aligned in their container, a valid bit count of 0 means all of it
+    let valid_bits = match format {
+        AudioFormat::MsAdpcm => None,
+        _ => match valid_bits {
+            Some(valid_bits @ 1..) if valid_bits > bit_depth => {
+                return Err(Error::FmtChunkError);
+            }
+            Some(valid_bits @ 1..) => Some(valid_bits),
+            _ => Some(bit_depth),
+        },
+    };
+
+    Ok(Fmt {
+        audio_format: format,
+        sample_rate,
+        channels,
+        sample_format: encoding,
+        extra,
+        channel_mask,
+        valid_bits,
+        ms_adpcm,
+        derived_sample_rate,
+        rate_consistent,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use super::{
+        AudioFormat, ChunkTag, CuePoint, EofMode, PackOrder, ParseMode, PartialOpen, Peak,
+        SeekPolicy, SpeakerLayout, Wav, WavProperties,
+    };
+    #[cfg(feature = "diagnostics")]
+    use crate::test_files::FlakyFile;
+    use crate::test_files::{FailingFile, LargeFile, ShortReadFile, StreamFile, TornReadFile};
+    use crate::{
+        AudioFile, Channels, Endian, MONO_16BIT, PlatformFile, PlatformFileError, Q15, STEREO_8BIT,
+        SampleFormat, SliceFile, TestFile, wav::Error,
+    };
+
+    #[test]
+    fn parse_fmt() {
+        let bytes = [
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x20, 0x00, // block align
+            0x10, 0x00, // bits per sample
+        ];
+
+        let fmt = super::parse_fmt(&bytes, bytes.len() as u64, ParseMode::Normal).unwrap();
+        assert!(fmt.audio_format == AudioFormat::Pcm);
+        assert!(fmt.sample_rate == 8_000);
+        assert!(fmt.sample_format == SampleFormat::I16);
+        assert!(fmt.channels == Channels::Mono);
+    }
+
+    #[test]
+    fn parse_le_16bit_8k_mono() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x32, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x20, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x08, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+            0x02, 0x00, // sample 3
+            0xff, 0xff, // sample 4
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+
+        assert!(wav.fmt.channels == Channels::Mono);
+        assert!(wav.fmt.sample_rate == 8_000);
+        assert!(wav.fmt.sample_format == SampleFormat::I16);
+
+        let mut sample = [0_u8; 2]; // size of one sample
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0x01, 0x00]);
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0xfe, 0xff]);
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0x02, 0x00]);
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0xff, 0xff]);
+    }
+
+    #[test]
+    fn parse_le_8bit_8k_stereo() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x32, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x02, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x20, 0x00, // block align
+            0x08, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x08, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1 L+R
+            0xfe, 0xff, // sample 2 L+R
+            0x02, 0x00, // sample 3 L+R
+            0xff, 0xff, // sample 4 L+R
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+
+        assert!(wav.fmt.channels == Channels::Stereo);
+        assert!(wav.fmt.sample_rate == 8_000);
+        assert!(wav.fmt.sample_format == SampleFormat::U8);
+
+        let mut sample = [0_u8; 2]; // size of one sample L+R
+        wav.read(&mut sample).unwrap(); // sample 1
+        assert!(sample == [0x01, 0x00]);
+        wav.read(&mut sample).unwrap(); // sample 2
+        assert!(sample == [0xfe, 0xff]);
+        wav.read(&mut sample).unwrap(); // sample 3
+        assert!(sample == [0x02, 0x00]);
+        wav.read(&mut sample).unwrap(); // sample 4
+        assert!(sample == [0xff, 0xff]);
+
+        assert!(wav.read(&mut sample) == Ok(0));
+    }
+
+    #[test]
+    fn chunk_after_data() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x32, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x64, 0x61, 0x74, 0x61, // data
+            0x08, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1 L+R
+            0xfe, 0xff, // sample 2 L+R
+            0x02, 0x00, // sample 3 L+R
+            0xff, 0xff, // sample 4 L+R
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x02, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x20, 0x00, // block align
+            0x08, 0x00, // bits per sample
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+
+        assert!(wav.fmt.channels == Channels::Stereo);
+        assert!(wav.fmt.sample_rate == 8_000);
+        assert!(wav.fmt.sample_format == SampleFormat::U8);
+
+        let mut sample = [0_u8; 2]; // size of one sample L+R
+        wav.read(&mut sample).unwrap(); // sample 1
+        assert!(sample == [0x01, 0x00]);
+        wav.read(&mut sample).unwrap(); // sample 2
+        assert!(sample == [0xfe, 0xff]);
+        wav.read(&mut sample).unwrap(); // sample 3
+        assert!(sample == [0x02, 0x00]);
+        wav.read(&mut sample).unwrap(); // sample 4
+        assert!(sample == [0xff, 0xff]);
+
+        assert!(wav.read(&mut sample) == Ok(0));
+    }
+
+    #[test]
+    fn data_chunk_overflow() {
+        const BYTES: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x32, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x00, 0x01, 0x00, 0x00, // data chunk size (larger than the file)
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+        ];
+        let mut wav = Wav::new(TestFile::from_bytes(BYTES)).unwrap();
+        assert!(wav.data_len() == BYTES.len() - 44);
+
+        let mut sample = [0_u8; 2];
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0x01, 0x00]);
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0xfe, 0xff]);
+        assert!(wav.is_eof());
+        assert!(wav.read(&mut sample) == Ok(0));
+
+        let strict = Wav::new_with_mode(TestFile::from_bytes(BYTES), ParseMode::Strict);
+        assert!(matches!(strict, Err(Error::DataChunkOverflow)));
+    }
+
+    #[test]
+    fn raw_fmt() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x32, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x20, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x08, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+            0x02, 0x00, // sample 3
+            0xff, 0xff, // sample 4
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+
+        let mut sample = [0_u8; 2];
+        wav.read(&mut sample).unwrap();
+
+        let mut fmt = [0_u8; 32];
+        assert!(wav.raw_fmt(&mut fmt) == Ok(16));
+        assert!(
+            fmt[..16]
+                == [
+                    0x01, 0x00, 0x01, 0x00, 0x40, 0x1f, 0x00, 0x00, 0x80, 0x3e, 0x00, 0x00, 0x20,
+                    0x00, 0x10, 0x00,
+                ]
+        );
+        assert!(wav.raw_fmt(&mut fmt[..8]) == Err(Error::ChunkSizeIncorrect));
+
+        // cursor is restored after copying the fmt chunk
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0xfe, 0xff]);
+    }
+
+    #[test]
+    fn raw_fmt_odd_length() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x34, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x13, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x01, 0x00, // cbSize
+            0xab, // extension
+            0x00, // pad byte
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+
+        // the declared 19 bytes, without the pad byte
+        let mut fmt = [0xcc_u8; 32];
+        assert!(wav.raw_fmt(&mut fmt) == Ok(19));
+        assert!(fmt[16..20] == [0x01, 0x00, 0xab, 0xcc]);
+        assert!(wav.raw_fmt(&mut fmt[..19]) == Ok(19));
+    }
+
+    #[test]
+    fn short_reads_stay_frame_aligned() {
+        let file = ShortReadFile(TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x32, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x02, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x20, 0x00, // block align
+            0x08, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x08, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1 L+R
+            0xfe, 0xff, // sample 2 L+R
+            0x02, 0x00, // sample 3 L+R
+            0xff, 0xff, // sample 4 L+R
+        ]));
+        let mut wav = Wav::new(file).unwrap();
+
+        // less than a frame reads nothing
+        let mut partial = [0xaa_u8; 1];
+        assert!(wav.read(&mut partial) == Ok(0));
+        assert!(partial == [0xaa]);
+        assert!(wav.played() == 0);
+
+        let mut frames = [0_u8; 3]; // one and a half frames
+        assert!(wav.read(&mut frames) == Ok(2));
+        assert!(frames[..2] == [0x01, 0x00]);
+        assert!(wav.read(&mut frames) == Ok(2));
+        assert!(frames[..2] == [0xfe, 0xff]);
+
+        let mut frames = [0_u8; 4]; // two frames
+        assert!(wav.read(&mut frames) == Ok(4));
+        assert!(frames == [0x02, 0x00, 0xff, 0xff]);
+
+        assert!(wav.read(&mut frames) == Ok(0));
+
+        // converted samples are held to whole frames too, 3 bytes is less than a 16 bit frame
+        wav.restart().unwrap();
+        wav.set_output_format(SampleFormat::I16);
+        let mut partial = [0xaa_u8; 3];
+        assert!(wav.read(&mut partial) == Ok(0));
+        assert!(wav.played() == 0);
+    }
+
+    #[test]
+    fn torn_reads_stay_frame_aligned() {
+        let file = TornReadFile {
+            file: TestFile::from_bytes(STEREO_8BIT),
+            torn: 0,
+            fail: false,
+        };
+        let mut wav = Wav::new(file).unwrap();
+        wav.get_mut().torn = 1;
+
+        // fails after half a frame, nothing is handed out and the position stays put
+        let mut frame = [0_u8; 2];
+        assert!(wav.read(&mut frame) == Err(Error::PlatformError(PlatformFileError::Io)));
+        assert!(wav.played() == 0);
+
+        let mut frames = [0_u8; 8];
+        assert!(wav.read(&mut frames) == Ok(8));
+        assert!(frames == STEREO_8BIT[44..]);
+    }
+
+    #[test]
+    fn data_len() {
+        let mut mono = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(mono.data_len() == 8);
+        assert!(mono.remaining() == 4);
+        mono.try_seek(3).unwrap();
+        assert!(mono.remaining() == 1);
+        assert!(mono.data_len() == 8);
+
+        let stereo = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        assert!(stereo.data_len() == 8);
+    }
+
+    #[test]
+    fn sync_position() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+
+        // move the file behind the parser's back to the last sample
+        wav.file.seek_from_start(wav.data_start + 6).unwrap();
+        assert!(wav.played() == 0);
+        wav.sync_position(6).unwrap();
+        assert!(wav.played() == 3);
+        assert!(!wav.is_eof());
+
+        let mut sample = [0_u8; 2];
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0xff, 0xff]);
+        assert!(wav.played() == 4);
+        assert!(wav.is_eof());
+
+        assert!(wav.sync_position(10) == Err(Error::PositionOutOfBounds));
+    }
+
+    #[test]
+    fn extensible_5_1_speaker_layout() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x54, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x28, 0x00, 0x00, 0x00, // fmt chunk size
+            0xfe, 0xff, // audio format (extensible)
+            0x06, 0x00, // channel count
+            0x80, 0xbb, 0x00, 0x00, // sample rate
+            0x00, 0x94, 0x11, 0x00, // byte rate
+            0x0c, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x16, 0x00, // extension size
+            0x10, 0x00, // valid bits per sample
+            0x3f, 0x00, 0x00, 0x00, // channel mask
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, // sub format (pcm)
+            0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71, // sub format guid
+            0x64, 0x61, 0x74, 0x61, // data
+            0x0c, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, 0x02, 0x00, 0x03, 0x00, // frame 1 FL FR FC
+            0x04, 0x00, 0x05, 0x00, 0x06, 0x00, // frame 1 LFE BL BR
+        ]);
+        let wav = Wav::new(file).unwrap();
+
+        assert!(wav.channels() == Channels::SURROUND_5_1);
+        assert!(wav.sample_format() == SampleFormat::I16);
+
+        let layout = wav.speaker_layout().unwrap();
+        assert!(layout == SpeakerLayout::SURROUND_5_1);
+        assert!(layout.count() == 6);
+        assert!(layout.contains(SpeakerLayout::STEREO));
+        assert!(layout.contains(SpeakerLayout::FRONT_CENTER | SpeakerLayout::LOW_FREQUENCY));
+        assert!(layout.contains(SpeakerLayout::BACK_LEFT | SpeakerLayout::BACK_RIGHT));
+        assert!(!layout.contains(SpeakerLayout::SIDE_LEFT));
+
+        let pcm = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(pcm.speaker_layout().is_none());
+    }
+
+    #[test]
+    fn chunk_reader() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(wav.chunks().len() == 3);
+
+        let mut raw = [0_u8; 16];
+        wav.raw_fmt(&mut raw).unwrap();
+
+        let fmt_chunk = *wav
+            .chunks()
+            .iter()
+            .find(|chunk| chunk.chunk == ChunkTag::Fmt)
+            .unwrap();
+        let mut reader = wav.chunk_reader(&fmt_chunk).unwrap();
+        assert!(reader.remaining() == 16);
+
+        // read in small pieces to exercise the chunk bounds
+        let mut contents = [0_u8; 16];
+        let mut byte = [0_u8; 3];
+        let mut len = 0;
+        loop {
+            let read = reader.read(&mut byte).unwrap();
+            if read == 0 {
+                break;
+            }
+            contents[len..len + read].copy_from_slice(&byte[..read]);
+            len += read;
+        }
+        drop(reader);
+        assert!(len == 16);
+        assert!(contents == raw);
+
+        let mut sample = [0_u8; 2];
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0x01, 0x00]);
+    }
+
+    #[test]
+    fn seek_policy_error() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        wav.set_seek_policy(SeekPolicy::Error);
+
+        assert!(wav.try_seek(5) == Err(Error::PositionOutOfBounds));
+        assert!(wav.try_seek(-1) == Err(Error::PositionOutOfBounds));
+
+        // a failed seek leaves the position untouched
+        wav.try_seek(3).unwrap();
+        assert!(wav.played() == 3);
+        wav.restart().unwrap();
+        assert!(wav.played() == 0);
+
+        let mut sample = [0_u8; 2];
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0x01, 0x00]);
+    }
+
+    #[test]
+    #[should_panic(expected = "failed to seek")]
+    fn seek_policy_panic() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        wav.set_seek_policy(SeekPolicy::Panic);
+        let _ = wav.try_seek(5);
+    }
+
+    #[test]
+    fn read_frames_stereo() {
+        let mut wav = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+
+        let mut frames = [(0_i16, 0_i16); 5];
+        assert!(wav.read_frames_stereo(&mut frames) == Ok(4));
+        assert!(
+            frames[..4]
+                == [
+                    (-32512, -32768),
+                    (32256, 32512),
+                    (-32256, -32768),
+                    (32512, 32512)
+                ]
+        );
+        assert!(wav.read_frames_stereo(&mut frames) == Ok(0));
+
+        let mut mono = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(mono.read_frames_stereo(&mut frames) == Err(Error::ChannelCountMismatch));
+    }
+
+    #[test]
+    fn read_planar_i16() {
+        let mut wav = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+
+        let (mut left, mut right) = ([0_i16; 4], [0_i16; 4]);
+        assert!(wav.read_planar_i16(&mut [&mut left, &mut right]) == Ok(4));
+        assert!(left == [-32512, 32256, -32256, 32512]);
+        assert!(right == [-32768, 32512, -32768, 32512]);
+        assert!(wav.read_planar_i16(&mut [&mut left, &mut right]) == Ok(0));
+
+        assert!(wav.read_planar_i16(&mut [&mut left]) == Err(Error::ChannelCountMismatch));
+    }
+
+    #[test]
+    fn output_format_u8_to_i16() {
+        let mut wav = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        wav.set_output_format(SampleFormat::I16);
+        assert!(wav.sample_format() == SampleFormat::I16);
+        assert!(wav.frame_size() == 4);
+        assert!(wav.data_len() == 16);
+
+        let mut frame = [0_u8; 4];
+        wav.read(&mut frame).unwrap();
+        assert!(frame == [0x00, 0x81, 0x00, 0x80]);
+        wav.read(&mut frame).unwrap();
+        assert!(frame == [0x00, 0x7e, 0x00, 0x7f]);
+        assert!(wav.played() == 4);
+
+        let mut frames = [0_u8; 16];
+        assert!(wav.read(&mut frames) == Ok(8));
+        assert!(frames[..8] == [0x00, 0x82, 0x00, 0x80, 0x00, 0x7f, 0x00, 0x7f]);
+        assert!(wav.read(&mut frames) == Ok(0));
+    }
+
+    #[test]
+    fn output_format_i24_to_i16() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x2a, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0xc0, 0x5d, 0x00, 0x00, // byte rate
+            0x03, 0x00, // block align
+            0x18, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x06, 0x00, 0x00, 0x00, // data chunk size
+            0x56, 0x34, 0x12, // sample 1
+            0xba, 0xdc, 0xfe, // sample 2
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+        assert!(wav.sample_format() == SampleFormat::I24);
+        wav.set_output_format(SampleFormat::I16);
+
+        let mut samples = [0_u8; 4];
+        assert!(wav.read(&mut samples) == Ok(4));
+        assert!(samples == [0x34, 0x12, 0xdc, 0xfe]);
+        assert!(wav.is_eof());
+    }
+
+    #[test]
+    fn cue_label() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x6a, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x20, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x08, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+            0x02, 0x00, // sample 3
+            0xff, 0xff, // sample 4
+            0x63, 0x75, 0x65, 0x20, // cue
+            0x1c, 0x00, 0x00, 0x00, // cue chunk size
+            0x01, 0x00, 0x00, 0x00, // cue point count
+            0x01, 0x00, 0x00, 0x00, // cue point id
+            0x00, 0x00, 0x00, 0x00, // play order position
+            0x64, 0x61, 0x74, 0x61, // data chunk id
+            0x00, 0x00, 0x00, 0x00, // chunk start
+            0x00, 0x00, 0x00, 0x00, // block start
+            0x02, 0x00, 0x00, 0x00, // sample offset
+            0x4c, 0x49, 0x53, 0x54, // LIST
+            0x16, 0x00, 0x00, 0x00, // list chunk size
+            0x61, 0x64, 0x74, 0x6c, // adtl
+            0x6c, 0x61, 0x62, 0x6c, // labl
+            0x0a, 0x00, 0x00, 0x00, // labl chunk size
+            0x01, 0x00, 0x00, 0x00, // cue point id
+            0x49, 0x6e, 0x74, 0x72, 0x6f, 0x00, // "Intro"
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+
+        assert!(
+            wav.cue_points()
+                == [CuePoint {
+                    id: 1,
+                    chunk_start: 0,
+                    block_start: 0,
+                    sample_offset: 2,
+                    frame: 2,
+                }]
+        );
+        assert!(wav.label_for_cue(1) == Some("Intro"));
+        assert!(wav.label_for_cue(2).is_none());
+
+        // parsing the metadata leaves the cursor at the first sample
+        let mut sample = [0_u8; 2];
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0x01, 0x00]);
+    }
+
+    #[test]
+    fn try_open_partial() {
+        // the first part ends inside the fmt chunk
+        let file = TestFile::from_bytes(&MONO_16BIT[..30]);
+        let Ok(PartialOpen::NeedMore {
+            mut file,
+            bytes_needed,
+        }) = Wav::try_open_partial(file)
+        else {
+            panic!("the fmt chunk is incomplete");
+        };
+        assert!(bytes_needed == 6);
+
+        file.contents = &MONO_16BIT[..44];
+        let Ok(PartialOpen::Ready(mut wav)) = Wav::try_open_partial(file) else {
+            panic!("the header has arrived");
+        };
+        assert!(wav.channels() == Channels::Mono);
+        assert!(wav.sample_rate() == 8_000);
+        let mut sample = [0_u8; 2];
+        assert!(wav.read(&mut sample) == Ok(0));
+
+        // the rest of the samples arrive
+        let mut file = wav.into_inner();
+        file.contents = MONO_16BIT;
+        let Ok(PartialOpen::Ready(mut wav)) = Wav::try_open_partial(file) else {
+            panic!("the header has arrived");
+        };
+        assert!(wav.read(&mut sample) == Ok(2));
+        assert!(sample == [0x01, 0x00]);
+    }
+
+    #[test]
+    fn from_header_bytes() {
+        let properties = WavProperties {
+            sample_rate: 8_000,
+            channels: Channels::Mono,
+            sample_format: SampleFormat::I16,
+            data_start: 44,
+            data_len: 8,
+        };
+        assert!(Wav::<TestFile>::from_header_bytes(MONO_16BIT) == Ok(properties));
+        // the samples themselves aren't needed
+        assert!(Wav::<TestFile>::from_header_bytes(&MONO_16BIT[..44]) == Ok(properties));
+        assert!(Wav::<TestFile>::from_header_bytes(&MONO_16BIT[..40]) == Err(Error::NeedMoreData));
+        assert!(Wav::<TestFile>::from_header_bytes(&MONO_16BIT[..30]) == Err(Error::NeedMoreData));
+        assert!(
+            Wav::<TestFile>::from_header_bytes(&STEREO_8BIT[1..]) == Err(Error::NoRiffChunkFound)
+        );
+    }
+
+    #[test]
+    fn sample_endian_override() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT))
+            .unwrap()
+            .with_sample_endian(Endian::Big);
+        assert!(wav.sample_format() == SampleFormat::I16);
+
+        let mut samples = [0_u8; 8];
+        assert!(wav.read(&mut samples) == Ok(8));
+        assert!(samples == [0x00, 0x01, 0xff, 0xfe, 0x00, 0x02, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn count_frames_unknown_size() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x00, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x20, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x00, 0x00, 0x00, 0x00, // data chunk size (unknown)
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+            0x02, 0x00, // sample 3
+            0xff, 0xff, // sample 4
+            0x01, 0x00, // sample 5
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+        assert!(wav.data_len() == 0);
+        assert!(wav.count_frames() == Ok(5));
+
+        let mut mono = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        let mut sample = [0_u8; 2];
+        mono.read(&mut sample).unwrap();
+        assert!(mono.count_frames() == Ok(4));
+
+        // cursor is restored after counting
+        mono.read(&mut sample).unwrap();
+        assert!(sample == [0xfe, 0xff]);
+    }
+
+    #[test]
+    fn fill_callback() {
+        let mut wav = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        wav.set_output_format(SampleFormat::I24);
+
+        let mut filled = [0_u8; 24];
+        let mut len = 0;
+        let mut calls = 0;
+        let frames = wav
+            .fill(3, |chunk| {
+                filled[len..len + chunk.len()].copy_from_slice(chunk);
+                len += chunk.len();
+                calls += 1;
+            })
+            .unwrap();
+        assert!(frames == 3);
+        assert!(len == 18);
+        assert!(calls == 1);
+
+        let mut plain = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        plain.set_output_format(SampleFormat::I24);
+        let mut expected = [0_u8; 18];
+        assert!(plain.read(&mut expected) == Ok(18));
+        assert!(filled[..18] == expected);
+
+        // only one frame is left
+        assert!(wav.fill(3, |_| {}) == Ok(1));
+        assert!(wav.fill(3, |_| {}) == Ok(0));
+    }
+
+    #[test]
+    fn new_streaming() {
+        let file = StreamFile(TestFile::from_bytes(STEREO_8BIT));
+        assert!(!file.can_seek());
+        let mut wav = Wav::new_streaming(file).unwrap();
+        assert!(wav.channels() == Channels::Stereo);
+        assert!(wav.sample_format() == SampleFormat::U8);
+        assert!(wav.data_len() == 8);
+
+        let mut frames = [0_u8; 8];
+        assert!(wav.read(&mut frames) == Ok(8));
+        assert!(frames == STEREO_8BIT[44..]);
+        assert!(wav.is_eof());
+
+        assert!(matches!(
+            Wav::new(StreamFile(TestFile::from_bytes(STEREO_8BIT))),
+            Err(Error::PlatformError(PlatformFileError::SeekOutofBounds))
+        ));
+
+        // data before fmt needs a seek back
+        let file = StreamFile(TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x32, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x64, 0x61, 0x74, 0x61, // data
+            0x02, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1 L+R
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x02, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x20, 0x00, // block align
+            0x08, 0x00, // bits per sample
+        ]));
+        assert!(matches!(
+            Wav::new_streaming(file),
+            Err(Error::SeekUnsupported)
+        ));
+    }
+
+    #[test]
+    fn format_depth_mismatch() {
+        let float_16bit = [
+            0x03, 0x00, // audio format (float)
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+        ];
+        assert!(matches!(
+            super::parse_fmt(&float_16bit, float_16bit.len() as u64, ParseMode::Normal),
+            Err(Error::FormatDepthMismatch)
+        ));
+
+        let pcm_64bit = [
+            0x01, 0x00, // audio format (pcm)
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x00, 0xfa, 0x00, 0x00, // byte rate
+            0x08, 0x00, // block align
+            0x40, 0x00, // bits per sample
+        ];
+        assert!(matches!(
+            super::parse_fmt(&pcm_64bit, pcm_64bit.len() as u64, ParseMode::Normal),
+            Err(Error::FormatDepthMismatch)
+        ));
+
+        let float_32bit = [
+            0x03, 0x00, // audio format (float)
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x00, 0x7d, 0x00, 0x00, // byte rate
+            0x04, 0x00, // block align
+            0x20, 0x00, // bits per sample
+        ];
+        let fmt =
+            super::parse_fmt(&float_32bit, float_32bit.len() as u64, ParseMode::Normal).unwrap();
+        assert!(fmt.audio_format == AudioFormat::IeeeFloat);
+        assert!(fmt.sample_format == SampleFormat::F32);
+
+        let float_64bit = [
+            0x03, 0x00, // audio format (float)
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x00, 0xfa, 0x00, 0x00, // byte rate
+            0x08, 0x00, // block align
+            0x40, 0x00, // bits per sample
+        ];
+        let fmt =
+            super::parse_fmt(&float_64bit, float_64bit.len() as u64, ParseMode::Strict).unwrap();
+        assert!(fmt.sample_format == SampleFormat::F64);
+    }
+
+    #[test]
+    fn new_fast() {
+        for fixture in [MONO_16BIT, STEREO_8BIT] {
+            let full = Wav::new(TestFile::from_bytes(fixture)).unwrap();
+            let mut fast = Wav::new_fast(TestFile::from_bytes(fixture)).unwrap();
+            assert!(fast.sample_rate() == full.sample_rate());
+            assert!(fast.channels() == full.channels());
+            assert!(fast.sample_format() == full.sample_format());
+            assert!(fast.data_start == full.data_start);
+            assert!(fast.data_len() == full.data_len());
+
+            let mut samples = [0_u8; 8];
+            assert!(fast.read(&mut samples) == Ok(8));
+            assert!(samples == fixture[44..]);
+        }
+
+        // data before fmt falls back to the full parse
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x32, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x64, 0x61, 0x74, 0x61, // data
+            0x02, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1 L+R
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x02, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x20, 0x00, // block align
+            0x08, 0x00, // bits per sample
+        ]);
+        let mut wav = Wav::new_fast(file).unwrap();
+        assert!(wav.channels() == Channels::Stereo);
+        let mut frame = [0_u8; 2];
+        assert!(wav.read(&mut frame) == Ok(2));
+        assert!(frame == [0x01, 0x00]);
+    }
+
+    #[test]
+    fn dither_i24_to_i16() {
+        let bytes: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x30, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0xc0, 0x5d, 0x00, 0x00, // byte rate
+            0x03, 0x00, // block align
+            0x18, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x0c, 0x00, 0x00, 0x00, // data chunk size
+            0x56, 0x34, 0x12, // sample 1
+            0xba, 0xdc, 0xfe, // sample 2
+            0x80, 0x00, 0x00, // sample 3
+            0xff, 0xff, 0x7f, // sample 4
+        ];
+        let mut wav = Wav::new(TestFile::from_bytes(bytes)).unwrap();
+        wav.set_output_format(SampleFormat::I16);
+        wav.set_dither(true);
+
+        let mut samples = [0_u8; 8];
+        assert!(wav.read(&mut samples) == Ok(8));
+        assert!(samples == [0x34, 0x12, 0xdd, 0xfe, 0x00, 0x00, 0xff, 0x7f]);
+
+        // widening conversions are left untouched
+        let mut wav = Wav::new(TestFile::from_bytes(bytes)).unwrap();
+        wav.set_output_format(SampleFormat::I32);
+        wav.set_dither(true);
+        let mut samples = [0_u8; 4];
+        assert!(wav.read(&mut samples) == Ok(4));
+        assert!(samples == [0x00, 0x56, 0x34, 0x12]);
+    }
+
+    #[test]
+    fn peak_chunk() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x48, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x03, 0x00, // audio format
+            0x02, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x00, 0xfa, 0x00, 0x00, // byte rate
+            0x08, 0x00, // block align
+            0x20, 0x00, // bits per sample
+            0x50, 0x45, 0x41, 0x4b, // PEAK
+            0x18, 0x00, 0x00, 0x00, // peak chunk size
+            0x01, 0x00, 0x00, 0x00, // version
+            0x00, 0x00, 0x00, 0x00, // timestamp
+            0x00, 0x00, 0x00, 0x3f, // left peak 0.5
+            0x01, 0x00, 0x00, 0x00, // left position
+            0x00, 0x00, 0x40, 0x3f, // right peak 0.75
+            0x00, 0x00, 0x00, 0x00, // right position
+            0x64, 0x61, 0x74, 0x61, // data
+            0x08, 0x00, 0x00, 0x00, // data chunk size
+            0x00, 0x00, 0x00, 0x00, // frame 1 L
+            0x00, 0x00, 0x40, 0xbf, // frame 1 R
+        ]);
+        let wav = Wav::new(file).unwrap();
+        let peak = wav.peak().unwrap();
+        assert!(peak.version == 1);
+        assert!(
+            peak.peaks
+                == [
+                    Peak {
+                        value: 0.5,
+                        position: 1
+                    },
+                    Peak {
+                        value: 0.75,
+                        position: 0
+                    }
+                ]
+        );
+
+        let wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(wav.peak().is_none());
+    }
+
+    #[test]
+    fn read_raw() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x34, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+            0x61, 0x62, 0x63, 0x64, // abcd
+            0x04, 0x00, 0x00, 0x00, // abcd chunk size
+            0x01, 0x02, 0x03, 0x04, // abcd data
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+        let mut samples = [0_u8; 8];
+        assert!(wav.read(&mut samples) == Ok(4));
+        assert!(wav.read(&mut samples) == Ok(0));
+
+        // the trailing chunk is past the data chunk
+        let mut trailing = [0_u8; 8];
+        assert!(wav.read_raw(&mut trailing) == Ok(8));
+        assert!(trailing == [0x61, 0x62, 0x63, 0x64, 0x04, 0x00, 0x00, 0x00]);
+
+        wav.seek_raw(56).unwrap();
+        let mut body = [0_u8; 4];
+        assert!(wav.read_raw(&mut body) == Ok(4));
+        assert!(body == [0x01, 0x02, 0x03, 0x04]);
+    }
+
+    #[test]
+    fn fmt_extension_size() {
+        let base = [
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+        ];
+        assert!(
+            super::parse_fmt(&base, 16, ParseMode::Normal)
+                .unwrap()
+                .extra
+                .is_none()
+        );
+
+        let mut bytes = [0_u8; 40];
+        bytes[..16].copy_from_slice(&base);
+        // cbSize = 0
+        let fmt = super::parse_fmt(&bytes[..18], 18, ParseMode::Normal).unwrap();
+        assert!(fmt.extra.unwrap().param_size == 0);
+
+        // an extensible header with cbSize = 22
+        bytes[0..2].copy_from_slice(&[0xfe, 0xff]);
+        bytes[16..18].copy_from_slice(&[0x16, 0x00]);
+        bytes[18..20].copy_from_slice(&[0x10, 0x00]);
+        bytes[20..24].copy_from_slice(&[0x04, 0x00, 0x00, 0x00]);
+        bytes[24..26].copy_from_slice(&[0x01, 0x00]);
+        let fmt = super::parse_fmt(&bytes, 40, ParseMode::Normal).unwrap();
+        assert!(fmt.extra.unwrap().param_size == 22);
+        assert!(fmt.audio_format == AudioFormat::Pcm);
+        assert!(fmt.channel_mask == Some(0x4));
+
+        // cbSize must account for the rest of the chunk in strict mode, otherwise it's
+        // clamped to the chunk
+        assert!(matches!(
+            super::parse_fmt(&bytes, 26, ParseMode::Strict),
+            Err(Error::FmtChunkError)
+        ));
+        for mode in [ParseMode::Normal, ParseMode::Lenient] {
+            let fmt = super::parse_fmt(&bytes, 26, mode).unwrap();
+            assert!(fmt.extra.unwrap().param_size == 8);
+        }
+    }
+
+    /// 8 kHz mono 16 bit wav with the fmt chunk `fmt` and two samples
+    fn wav_with_fmt(fmt: &[u8]) -> std::vec::Vec<u8> {
+        let mut bytes = std::vec::Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(4 + 8 + fmt.len() as u32 + 12).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&(fmt.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(fmt);
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&4_u32.to_le_bytes());
+        bytes.extend_from_slice(&[0x01, 0x00, 0xfe, 0xff]);
+        bytes
+    }
+
+    #[test]
+    fn fmt_cb_size_files() {
+        let mut fmt = [0_u8; 40];
+        fmt[..16].copy_from_slice(&[
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+        ]);
+
+        // 18 bytes with cbSize = 0
+        let bytes = wav_with_fmt(&fmt[..18]);
+        let mut wav = Wav::new_with_mode(SliceFile::new(&bytes), ParseMode::Strict).unwrap();
+        assert!(wav.fmt.extra.as_ref().unwrap().param_size == 0);
+        let mut samples = [0_i16; 2];
+        assert!(wav.read_samples(&mut samples) == Ok(2));
+        assert!(samples == [1, -2]);
+
+        // 40 bytes extensible with cbSize = 22
+        let mut extensible = fmt;
+        extensible[0..2].copy_from_slice(&[0xfe, 0xff]);
+        extensible[16..18].copy_from_slice(&[0x16, 0x00]);
+        extensible[18..20].copy_from_slice(&[0x10, 0x00]);
+        extensible[20..24].copy_from_slice(&[0x04, 0x00, 0x00, 0x00]);
+        extensible[24..26].copy_from_slice(&[0x01, 0x00]);
+        let bytes = wav_with_fmt(&extensible);
+        let mut wav = Wav::new_with_mode(SliceFile::new(&bytes), ParseMode::Strict).unwrap();
+        assert!(wav.fmt.extra.as_ref().unwrap().param_size == 22);
+        assert!(wav.speaker_layout() == Some(SpeakerLayout::FRONT_CENTER));
+        assert!(wav.read_samples(&mut samples) == Ok(2));
+        assert!(samples == [1, -2]);
+
+        // 20 bytes with cbSize = 0, only strict mode rejects the mismatch
+        let bytes = wav_with_fmt(&fmt[..20]);
+        assert!(matches!(
+            Wav::new_with_mode(SliceFile::new(&bytes), ParseMode::Strict),
+            Err(Error::FmtChunkError)
+        ));
+        for mode in [ParseMode::Normal, ParseMode::Lenient] {
+            let mut wav = Wav::new_with_mode(SliceFile::new(&bytes), mode).unwrap();
+            assert!(wav.fmt.extra.as_ref().unwrap().param_size == 0);
+            assert!(wav.read_samples(&mut samples) == Ok(2));
+            assert!(samples == [1, -2]);
+        }
+
+        // cbSize = 22 in a 20 byte chunk is clamped to the 2 bytes there are
+        fmt[16..18].copy_from_slice(&[0x16, 0x00]);
+        let bytes = wav_with_fmt(&fmt[..20]);
+        assert!(matches!(
+            Wav::new_with_mode(SliceFile::new(&bytes), ParseMode::Strict),
+            Err(Error::FmtChunkError)
+        ));
+        for mode in [ParseMode::Normal, ParseMode::Lenient] {
+            let wav = Wav::new_with_mode(SliceFile::new(&bytes), mode).unwrap();
+            assert!(wav.fmt.extra.as_ref().unwrap().param_size == 2);
+        }
+    }
+
+    #[test]
+    fn probe() {
+        let mut file = TestFile::from_bytes(STEREO_8BIT);
+        let properties = Wav::probe(&mut file).unwrap();
+        assert!(properties.channels == Channels::Stereo);
+        assert!(properties.sample_format == SampleFormat::U8);
+        assert!(properties.data_start == 44);
+        assert!(properties.data_len == 8);
+
+        // the file is left at the first sample
+        let mut sample = [0_u8; 1];
+        file.read(&mut sample).unwrap();
+        assert!(sample[0] == STEREO_8BIT[44]);
+    }
+
+    #[test]
+    fn byte_rate() {
+        let mono = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(mono.byte_rate() == 16_000);
+
+        let mut stereo = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        assert!(stereo.byte_rate() == 16_000);
+        stereo.set_output_format(SampleFormat::I16);
+        assert!(stereo.byte_rate() == 32_000);
+    }
+
+    #[test]
+    fn bitrate_bps() {
+        let mono = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(mono.bitrate_bps() == 128_000);
+    }
+
+    #[test]
+    fn strict_clamps_chunk_inside_data() {
+        let bytes: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x28, 0x00, 0x00, 0x00, // chunk size, ends before the LIST chunk
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x16, 0x00, 0x00, 0x00, // data chunk size, covers the LIST chunk
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+            0x4c, 0x49, 0x53, 0x54, // LIST
+            0x0a, 0x00, 0x00, 0x00, // list chunk size
+            0x49, 0x4e, 0x46, 0x4f, // INFO
+            0x61, 0x62, 0x63, 0x64, 0x65, 0x66, // list data
+        ];
+        let mut strict =
+            Wav::new_with_mode(TestFile::from_bytes(bytes), ParseMode::Strict).unwrap();
+        assert!(strict.data_len() == 4);
+        assert!(
+            strict
+                .chunks()
+                .iter()
+                .any(|chunk| chunk.chunk == ChunkTag::List)
+        );
+        let mut samples = [0_u8; 8];
+        assert!(strict.read(&mut samples) == Ok(4));
+        assert!(samples[..4] == [0x01, 0x00, 0xfe, 0xff]);
+        assert!(strict.is_eof());
+
+        // normal mode trusts the declared size
+        let normal = Wav::new(TestFile::from_bytes(bytes)).unwrap();
+        assert!(normal.data_len() == 22);
+
+        // samples that happen to spell a chunk tag are still samples
+        let bytes: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x2e, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x0a, 0x00, 0x00, 0x00, // data chunk size
+            0x4c, 0x49, 0x53, 0x54, // samples 1 and 2, spell LIST
+            0x02, 0x00, 0x00, 0x00, // samples 3 and 4, a size running to the end
+            0x01, 0x00, // sample 5
+        ];
+        let strict = Wav::new_with_mode(TestFile::from_bytes(bytes), ParseMode::Strict).unwrap();
+        assert!(strict.data_len() == 10);
+    }
+
+    #[test]
+    fn into_inner() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(wav.get_ref().current_pos == 44);
+
+        let mut samples = [0_u8; 4];
+        wav.read(&mut samples).unwrap();
+        assert!(wav.get_mut().current_pos == 48);
+
+        let file = wav.into_inner();
+        assert!(file.current_pos == 48);
+    }
+
+    #[test]
+    fn wrong_riff_size() {
+        let bytes: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x00, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+        ];
+        assert!(Wav::<TestFile>::from_header_bytes(bytes).is_ok());
+
+        for mut wav in [
+            Wav::new(TestFile::from_bytes(bytes)).unwrap(),
+            Wav::new_fast(TestFile::from_bytes(bytes)).unwrap(),
+        ] {
+            assert!(wav.data_len() == 4);
+            let mut samples = [0_u8; 8];
+            assert!(wav.read(&mut samples) == Ok(4));
+            assert!(samples[..4] == [0x01, 0x00, 0xfe, 0xff]);
+        }
+    }
+
+    #[test]
+    fn read_frame() {
+        let mut mono = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        let mut frame = [0_u8; 4];
+        for expected in MONO_16BIT[44..].chunks(2) {
+            assert!(mono.read_frame(&mut frame) == Ok(true));
+            assert!(frame[..2] == *expected);
+        }
+        assert!(mono.read_frame(&mut frame) == Ok(false));
+
+        let mut stereo = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        let mut frames = 0;
+        while stereo.read_frame(&mut frame) == Ok(true) {
+            assert!(frame[..2] == STEREO_8BIT[44 + frames * 2..][..2]);
+            frames += 1;
+        }
+        assert!(frames == 4);
+    }
+
+    #[test]
+    fn byte_rate_mismatch() {
+        let bytes: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x28, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x44, 0xac, 0x00, 0x00, // byte rate (should be 16000)
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+        ];
+        let strict = Wav::new_with_mode(TestFile::from_bytes(bytes), ParseMode::Strict);
+        assert!(matches!(strict, Err(Error::ByteRateMismatch)));
+
+        // normal mode trusts the sample rate
+        let wav = Wav::new(TestFile::from_bytes(bytes)).unwrap();
+        assert!(wav.sample_rate() == 8_000);
+        assert!(wav.byte_rate() == 16_000);
+        assert!(wav.derived_sample_rate() == Some(22_050));
+        assert!(!wav.rate_consistent());
+    }
+
+    #[test]
+    fn read_samples_q15() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x2c, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x03, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x00, 0x7d, 0x00, 0x00, // byte rate
+            0x04, 0x00, // block align
+            0x20, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x0c, 0x00, 0x00, 0x00, // data chunk size
+            0x00, 0x00, 0x80, 0x3f, // sample 1 (1.0)
+            0x00, 0x00, 0x80, 0xbf, // sample 2 (-1.0)
+            0x00, 0x00, 0x00, 0x3f, // sample 3 (0.5)
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+        let mut samples = [Q15::default(); 4];
+        assert!(wav.read_samples_q15(&mut samples) == Ok(3));
+        assert!(samples[..3] == [Q15::MAX, Q15::MIN, Q15(0x4000)]);
+        assert!(samples[2].to_f32() == 0.5);
+
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(wav.read_samples_q15(&mut samples) == Ok(4));
+        assert!(samples == [Q15(1), Q15(-2), Q15(2), Q15(-1)]);
+    }
+
+    #[test]
+    fn read_ms() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        wav.set_eof_mode(EofMode::Loop);
+        let mut samples = [0_u8; 20];
+        // 8 frames at 8 kHz, looping over the 4 frame fixture
+        assert!(wav.read_ms(1, &mut samples) == Ok(8));
+        assert!(samples[..16] == MONO_16BIT[44..].repeat(2));
+        assert!(samples[16..] == [0; 4]);
+
+        // fewer at EOF
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(wav.read_ms(1, &mut samples) == Ok(4));
+    }
+
+    #[test]
+    fn read_samples() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        let mut samples = [0_i16; 4];
+        assert!(wav.read_samples(&mut samples) == Ok(4));
+        assert!(samples == [1, -2, 2, -1]);
+
+        let mut wav = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        let mut samples = [0_f32; 8];
+        assert!(wav.read_samples(&mut samples) == Ok(8));
+        for (sample, byte) in samples.iter().zip(&STEREO_8BIT[44..]) {
+            assert!(*sample == (*byte as f32 - 128.0) / 128.0);
+        }
+    }
+
+    #[test]
+    fn multiple_data_chunks() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x3c, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+            0x61, 0x62, 0x63, 0x64, // abcd
+            0x02, 0x00, 0x00, 0x00, // abcd chunk size
+            0x00, 0x00, // abcd data
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x02, 0x00, // sample 3
+            0xff, 0xff, // sample 4
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+        assert!(wav.data_len() == 8);
+
+        // a single read spans both chunks
+        let mut samples = [0_u8; 8];
+        assert!(wav.read(&mut samples) == Ok(8));
+        assert!(samples == [0x01, 0x00, 0xfe, 0xff, 0x02, 0x00, 0xff, 0xff]);
+        assert!(wav.played() == 4);
+        assert!(wav.is_eof());
+
+        // seeks map into the second chunk
+        wav.try_seek(-1).unwrap();
+        let mut sample = [0_u8; 2];
+        assert!(wav.read(&mut sample) == Ok(2));
+        assert!(sample == [0xff, 0xff]);
+        assert!(wav.count_frames() == Ok(4));
+
+        wav.restart().unwrap();
+        wav.try_seek(2).unwrap();
+        assert!(wav.read(&mut sample) == Ok(2));
+        assert!(sample == [0x02, 0x00]);
+    }
+
+    #[test]
+    fn file_too_short() {
+        let bytes: &[u8] = &[0x52, 0x49, 0x46, 0x46];
+        assert!(matches!(
+            Wav::new(TestFile::from_bytes(bytes)),
+            Err(Error::FileTooShort)
+        ));
+        assert!(matches!(
+            Wav::new_fast(TestFile::from_bytes(bytes)),
+            Err(Error::FileTooShort)
+        ));
+        assert!(matches!(
+            Wav::new(TestFile::from_bytes(&[])),
+            Err(Error::FileTooShort)
+        ));
+    }
+
+    #[test]
+    fn id3v1() {
+        /// copy `text` into `bytes` at `offset`
+        const fn put<const N: usize>(mut bytes: [u8; N], offset: usize, text: &[u8]) -> [u8; N] {
+            let mut i = 0;
+            while i < text.len() {
+                bytes[offset + i] = text[i];
+                i += 1;
+            }
+            bytes
+        }
+        const TAGGED: [u8; 180] = {
+            let bytes = put([0; 180], 0, MONO_16BIT);
+            let bytes = put(bytes, 52, b"TAG");
+            let bytes = put(bytes, 55, b"Title");
+            // space padded like some taggers
+            let bytes = put(bytes, 85, b"Artist                        ");
+            let bytes = put(bytes, 115, b"Album");
+            put(bytes, 145, b"1999")
+        };
+
+        let mut wav = Wav::new(TestFile::from_bytes(&TAGGED)).unwrap();
+        let mut sample = [0_u8; 2];
+        wav.read(&mut sample).unwrap();
+
+        let tag = wav.id3v1().unwrap().unwrap();
+        assert!(tag.title == "Title");
+        assert!(tag.artist == "Artist");
+        assert!(tag.album == "Album");
+        assert!(tag.year == "1999");
+
+        // the tag sits outside the data chunk and the cursor is restored
+        assert!(wav.data_len() == 8);
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0xfe, 0xff]);
+
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(wav.id3v1() == Ok(None));
+    }
+
+    #[test]
+    fn load_to_ram() {
+        let wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(matches!(
+            wav.load_to_ram::<4>(),
+            Err(Error::ChunkSizeIncorrect)
+        ));
+
+        let wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        let mut ram = wav.load_to_ram::<16>().unwrap();
+        assert!(ram.as_bytes() == &MONO_16BIT[44..]);
+        assert!(ram.channels() == Channels::Mono);
+        assert!(ram.total_frames() == 4);
+
+        let mut samples = [0_i16; 4];
+        for _ in 0..2 {
+            assert!(ram.read_samples(&mut samples) == Ok(4));
+            assert!(samples == [1, -2, 2, -1]);
+            assert!(ram.is_eof());
+            ram.restart().unwrap();
+        }
+    }
+
+    #[test]
+    fn channel_reader() {
+        let mut wav = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        assert!(matches!(wav.channel(2), Err(Error::ChannelOutOfRange)));
+
+        let mut right = wav.channel(1).unwrap();
+        assert!(right.channels() == Channels::Mono);
+        assert!(right.total_frames() == 4);
+        let mut samples = [0_u8; 8];
+        assert!(right.read(&mut samples) == Ok(4));
+        assert!(
+            samples[..4]
+                == [
+                    STEREO_8BIT[45],
+                    STEREO_8BIT[47],
+                    STEREO_8BIT[49],
+                    STEREO_8BIT[51]
+                ]
+        );
+        assert!(right.is_eof());
+    }
+
+    #[test]
+    fn split_channels() {
+        let wav = RefCell::new(Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap());
+        let (mut left, mut right) = Wav::split_channels(&wav).unwrap();
+        assert!(left.channels() == Channels::Mono);
+        assert!(left.total_frames() == 4);
+
+        // the views aren't read in lockstep
+        let mut samples = [0_u8; 4];
+        assert!(left.read(&mut samples[..3]) == Ok(3));
+        assert!(samples[..3] == [0x01, 0xfe, 0x02]);
+        assert!(right.read(&mut samples) == Ok(4));
+        assert!(samples == [0x00, 0xff, 0x00, 0xff]);
+        assert!(right.is_eof());
+        assert!(left.read(&mut samples) == Ok(1));
+        assert!(samples[0] == 0xff);
+
+        right.restart().unwrap();
+        assert!(right.read(&mut samples[..1]) == Ok(1));
+        assert!(samples[0] == 0x00);
+
+        let mono = RefCell::new(Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap());
+        assert!(matches!(
+            Wav::split_channels(&mono),
+            Err(Error::ChannelCountMismatch)
+        ));
+    }
+
+    #[test]
+    fn eof_mode() {
+        let mut samples = [0xaa_u8; 12];
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(wav.read(&mut samples) == Ok(8));
+        assert!(wav.read(&mut samples) == Ok(0));
+
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        wav.set_eof_mode(EofMode::Loop);
+        assert!(wav.read(&mut samples) == Ok(8));
+        assert!(wav.read(&mut samples) == Ok(8));
+        assert!(samples[..8] == MONO_16BIT[44..]);
+        assert!(wav.played() == 4);
+
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        wav.set_eof_mode(EofMode::Silence);
+        assert!(wav.read(&mut samples) == Ok(8));
+        for _ in 0..2 {
+            samples = [0xaa; 12];
+            assert!(wav.read(&mut samples) == Ok(12));
+            assert!(samples == [0; 12]);
+        }
+        assert!(wav.is_eof());
+    }
+
+    #[test]
+    fn waveform_overview() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        let mut sample = [0_u8; 2];
+        wav.read(&mut sample).unwrap();
+
+        let mut buckets = [(0, 0); 2];
+        wav.waveform_overview(&mut buckets).unwrap();
+        assert!(buckets == [(-2, 1), (-1, 2)]);
+
+        let mut buckets = [(1, 1); 8];
+        wav.waveform_overview(&mut buckets).unwrap();
+        assert!(buckets[..2] == [(1, 1), (0, 0)]);
+
+        // the cursor is restored
+        assert!(wav.played() == 1);
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0xfe, 0xff]);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn larger_than_4gib() {
+        const HEADER: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0xff, 0xff, 0xff, 0xff, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
             0x01, 0x00, // audio format
             0x01, 0x00, // channel count
             0x40, 0x1f, 0x00, 0x00, // sample rate
             0x80, 0x3e, 0x00, 0x00, // byte rate
-            0x20, 0x00, // block align
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0xfe, 0xff, 0xff, 0xff, // data chunk size
+            0x01, 0x00, // sample 1
+        ];
+        // a second data chunk past the 4 GiB mark
+        const SECOND: u64 = 44 + 0xffff_fffe;
+        const TAIL: &[u8] = &[
+            0x64, 0x61, 0x74, 0x61, // data
+            0xfe, 0xff, 0xff, 0xff, // data chunk size
+            0x02, 0x00, // sample 1
+        ];
+        let file = LargeFile {
+            regions: &[(0, HEADER), (SECOND, TAIL)],
+            len: SECOND + 8 + 0xffff_fffe,
+            pos: 0,
+        };
+        let mut wav = Wav::new(file).unwrap();
+        assert!(wav.data_len() == 2 * 0xffff_fffe);
+        assert!(wav.total_frames() == 0xffff_fffe);
+
+        // seek to the first sample of the second chunk
+        wav.try_seek(0x7fff_ffff).unwrap();
+        assert!(wav.played() == 0x7fff_ffff);
+        let mut sample = [0_u8; 2];
+        assert!(wav.read(&mut sample) == Ok(2));
+        assert!(sample == [0x02, 0x00]);
+        assert!(wav.get_mut().pos == SECOND + 10);
+        assert!(wav.position_in_data() == 0x1_0000_0000);
+    }
+
+    #[test]
+    fn validate() {
+        let mut file = TestFile::from_bytes(MONO_16BIT);
+        file.seek_from_start(20).unwrap();
+        assert!(Wav::validate(&mut file) == Ok(()));
+        // rewound so it can be parsed
+        assert!(Wav::new(file).is_ok());
+
+        let mut truncated = TestFile::from_bytes(&MONO_16BIT[..48]);
+        assert!(Wav::validate(&mut truncated) == Err(Error::DataChunkOverflow));
+
+        let mut truncated = TestFile::from_bytes(&MONO_16BIT[..8]);
+        assert!(Wav::validate(&mut truncated) == Err(Error::FileTooShort));
+
+        let mut list_overflow = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x2e, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x02, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1
+            0x4c, 0x49, 0x53, 0x54, // LIST
+            0x20, 0x00, 0x00, 0x00, // list chunk size
+            0x61, 0x64, 0x74, 0x6c, // adtl
+        ]);
+        assert!(Wav::validate(&mut list_overflow) == Err(Error::ChunkOverflow));
+    }
+
+    #[test]
+    fn fmt_missing_cb_size() {
+        const MISSING_CB_SIZE: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x28, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x12, 0x00, 0x00, 0x00, // fmt chunk size, but cbSize is missing
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+        ];
+
+        let mut wav = Wav::new(TestFile::from_bytes(MISSING_CB_SIZE)).unwrap();
+        assert!(wav.sample_rate() == 8_000);
+        assert!(wav.sample_format() == SampleFormat::I16);
+        assert!(wav.fmt.extra.is_none());
+        assert!(wav.data_len() == 4);
+        let mut samples = [0_u8; 4];
+        assert!(wav.read(&mut samples) == Ok(4));
+        assert!(samples == [0x01, 0x00, 0xfe, 0xff]);
+
+        let mut wav =
+            Wav::new_streaming(StreamFile(TestFile::from_bytes(MISSING_CB_SIZE))).unwrap();
+        assert!(wav.data_len() == 4);
+        assert!(wav.read(&mut samples) == Ok(4));
+        assert!(samples == [0x01, 0x00, 0xfe, 0xff]);
+
+        let properties = Wav::probe(&mut TestFile::from_bytes(MISSING_CB_SIZE)).unwrap();
+        assert!(properties.data_start == 44);
+        assert!(Wav::<TestFile>::from_header_bytes(MISSING_CB_SIZE) == Ok(properties));
+    }
+
+    #[test]
+    fn wavl_silence() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x4c, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x4c, 0x49, 0x53, 0x54, // LIST
+            0x28, 0x00, 0x00, 0x00, // list chunk size
+            0x77, 0x61, 0x76, 0x6c, // wavl
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+            0x73, 0x6c, 0x6e, 0x74, // slnt
+            0x04, 0x00, 0x00, 0x00, // slnt chunk size
+            0x02, 0x00, 0x00, 0x00, // silent frames
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x02, 0x00, // sample 5
+            0xff, 0xff, // sample 6
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+        assert!(wav.data_len() == 12);
+        assert!(wav.count_frames() == Ok(6));
+
+        let mut samples = [0xaa_u8; 12];
+        assert!(wav.read(&mut samples) == Ok(12));
+        assert!(samples == [0x01, 0x00, 0xfe, 0xff, 0, 0, 0, 0, 0x02, 0x00, 0xff, 0xff]);
+        assert!(wav.played() == 6);
+        assert!(wav.is_eof());
+
+        // seeking into the silence reads zeros until the next data chunk
+        wav.try_seek(-3).unwrap();
+        let mut samples = [0xaa_u8; 4];
+        assert!(wav.read(&mut samples) == Ok(4));
+        assert!(samples == [0, 0, 0x02, 0x00]);
+    }
+
+    #[test]
+    fn position_in_data() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(wav.position_in_data() == 0);
+
+        for (offset, position) in [(2, 4), (-1, 2), (2, 6), (-3, 0), (4, 8)] {
+            wav.try_seek(offset).unwrap();
+            assert!(wav.position_in_data() == position);
+        }
+        assert!(wav.is_eof());
+
+        wav.restart().unwrap();
+        let mut sample = [0_u8; 2];
+        wav.read(&mut sample).unwrap();
+        assert!(wav.position_in_data() == 2);
+        wav.skip_frames(2).unwrap();
+        assert!(wav.position_in_data() == 6);
+    }
+
+    #[test]
+    fn seek_between_cues() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x84, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x0c, 0x00, 0x00, 0x00, // data chunk size
+            0x00, 0x00, 0x01, 0x00, 0x02, 0x00, // samples 0 to 2
+            0x03, 0x00, 0x04, 0x00, 0x05, 0x00, // samples 3 to 5
+            0x63, 0x75, 0x65, 0x20, // cue
+            0x4c, 0x00, 0x00, 0x00, // cue chunk size
+            0x03, 0x00, 0x00, 0x00, // cue point count
+            0x02, 0x00, 0x00, 0x00, // cue point id
+            0x00, 0x00, 0x00, 0x00, // play order position
+            0x64, 0x61, 0x74, 0x61, // data chunk id
+            0x00, 0x00, 0x00, 0x00, // chunk start
+            0x00, 0x00, 0x00, 0x00, // block start
+            0x03, 0x00, 0x00, 0x00, // sample offset
+            0x01, 0x00, 0x00, 0x00, // cue point id
+            0x00, 0x00, 0x00, 0x00, // play order position
+            0x64, 0x61, 0x74, 0x61, // data chunk id
+            0x00, 0x00, 0x00, 0x00, // chunk start
+            0x00, 0x00, 0x00, 0x00, // block start
+            0x01, 0x00, 0x00, 0x00, // sample offset
+            0x03, 0x00, 0x00, 0x00, // cue point id
+            0x00, 0x00, 0x00, 0x00, // play order position
+            0x64, 0x61, 0x74, 0x61, // data chunk id
+            0x00, 0x00, 0x00, 0x00, // chunk start
+            0x00, 0x00, 0x00, 0x00, // block start
+            0x05, 0x00, 0x00, 0x00, // sample offset
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+        wav.try_seek(2).unwrap();
+
+        let mut sample = [0_u8; 2];
+        assert!(wav.seek_next_cue().map(|cue| cue.id) == Ok(2));
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0x03, 0x00]);
+        assert!(wav.seek_next_cue().map(|cue| cue.id) == Ok(3));
+        assert!(wav.seek_next_cue() == Err(Error::NoCuePoint));
+        assert!(wav.position_in_data() == 10);
+
+        assert!(wav.seek_prev_cue().map(|cue| cue.id) == Ok(2));
+        assert!(wav.seek_prev_cue().map(|cue| cue.id) == Ok(1));
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0x01, 0x00]);
+        wav.try_seek(-1).unwrap();
+        assert!(wav.seek_prev_cue() == Err(Error::NoCuePoint));
+        assert!(wav.position_in_data() == 2);
+    }
+
+    #[test]
+    fn cue_in_wavl() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x70, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
             0x10, 0x00, // bits per sample
+            0x4c, 0x49, 0x53, 0x54, // LIST
+            0x28, 0x00, 0x00, 0x00, // list chunk size
+            0x77, 0x61, 0x76, 0x6c, // wavl
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+            0x73, 0x6c, 0x6e, 0x74, // slnt
+            0x04, 0x00, 0x00, 0x00, // slnt chunk size
+            0x02, 0x00, 0x00, 0x00, // silent frames
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x02, 0x00, // sample 5
+            0xff, 0xff, // sample 6
+            0x63, 0x75, 0x65, 0x20, // cue
+            0x1c, 0x00, 0x00, 0x00, // cue chunk size
+            0x01, 0x00, 0x00, 0x00, // cue point count
+            0x07, 0x00, 0x00, 0x00, // cue point id
+            0x00, 0x00, 0x00, 0x00, // play order position
+            0x64, 0x61, 0x74, 0x61, // data chunk id
+            0x18, 0x00, 0x00, 0x00, // chunk start, the second data chunk
+            0x00, 0x00, 0x00, 0x00, // block start
+            0x01, 0x00, 0x00, 0x00, // sample offset
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+
+        // the frames of the first data chunk and the silence come before it
+        let cue = wav.cue_points()[0];
+        assert!(cue.chunk_start == 24);
+        assert!(cue.sample_offset == 1);
+        assert!(cue.frame == 5);
+
+        wav.try_seek(cue.frame as i64).unwrap();
+        let mut sample = [0_u8; 2];
+        assert!(wav.read(&mut sample) == Ok(2));
+        assert!(sample == [0xff, 0xff]);
+    }
+
+    #[test]
+    fn i24_round_trip() {
+        // the little endian encoding a writer would produce, 3 bytes a sample
+        let samples = [0x1234_5600_u32 as i32, 0xfedc_ba00_u32 as i32, 0x7fff_ff00];
+        let mut encoded = [0_u8; 9];
+        for (sample, out) in samples.iter().zip(encoded.chunks_exact_mut(3)) {
+            SampleFormat::I24.write_i32(*sample, out);
+        }
+        assert!(encoded == [0x56, 0x34, 0x12, 0xba, 0xdc, 0xfe, 0xff, 0xff, 0x7f]);
+
+        const I24_PADDED: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x38, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0xc0, 0x5d, 0x00, 0x00, // byte rate
+            0x03, 0x00, // block align
+            0x18, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x09, 0x00, 0x00, 0x00, // data chunk size
+            0x56, 0x34, 0x12, // sample 1
+            0xba, 0xdc, 0xfe, // sample 2
+            0xff, 0xff, 0x7f, // sample 3
+            0x00, // pad byte
+            0x61, 0x62, 0x63, 0x64, // abcd
+            0x02, 0x00, 0x00, 0x00, // abcd chunk size
+            0x00, 0x00, // abcd data
+        ];
+        let mut wav =
+            Wav::new_with_mode(TestFile::from_bytes(I24_PADDED), ParseMode::Strict).unwrap();
+        assert!(wav.byte_rate() == 24_000);
+        assert!(wav.frame_size() == 3);
+        // the pad byte isn't a sample
+        assert!(wav.data_len() == 9);
+        assert!(
+            Wav::probe(&mut TestFile::from_bytes(I24_PADDED))
+                .unwrap()
+                .data_len
+                == 9
+        );
+        assert!(wav.chunks().last().unwrap().chunk == ChunkTag::Unknown(*b"abcd"));
+
+        let mut read = [0_i32; 4];
+        assert!(wav.read_samples(&mut read) == Ok(3));
+        assert!(read[..3] == samples);
+        assert!(wav.is_eof());
+    }
+
+    #[test]
+    fn skip_frames() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        wav.skip_frames(2).unwrap();
+        assert!(wav.played() == 2);
+        let mut sample = [0_u8; 2];
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0x02, 0x00]);
+
+        // clamped at EOF
+        wav.skip_frames(8).unwrap();
+        assert!(wav.played() == 4);
+        assert!(wav.is_eof());
+
+        let file = StreamFile(TestFile::from_bytes(MONO_16BIT));
+        let mut wav = Wav::new_streaming(file).unwrap();
+        wav.skip_frames(3).unwrap();
+        assert!(wav.played() == 3);
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0xff, 0xff]);
+        wav.skip_frames(8).unwrap();
+        assert!(wav.is_eof());
+    }
+
+    #[test]
+    fn ms_adpcm() {
+        const MS_ADPCM: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x58, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x32, 0x00, 0x00, 0x00, // fmt chunk size
+            0x02, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0xe0, 0x2e, 0x00, 0x00, // byte rate
+            0x09, 0x00, // block align
+            0x04, 0x00, // bits per sample
+            0x20, 0x00, // extension size
+            0x06, 0x00, // samples per block
+            0x07, 0x00, // coefficient count
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0xff, // coefficients
+            0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x40, 0x00, //
+            0xf0, 0x00, 0x00, 0x00, 0xcc, 0x01, 0x30, 0xff, //
+            0x88, 0x01, 0x18, 0xff, //
+            0x64, 0x61, 0x74, 0x61, // data
+            0x12, 0x00, 0x00, 0x00, // data chunk size
+            0x00, 0x10, 0x00, 0x64, 0x00, 0x32, 0x00, // block 1 header
+            0x12, 0x3f, // block 1 samples
+            0x01, 0x20, 0x00, 0x38, 0xff, 0x9c, 0xff, // block 2 header
+            0x7a, 0x81, // block 2 samples
         ];
 
-        let fmt = super::parse_fmt(&bytes).unwrap();
-        assert!(fmt.audio_format == AudioFormat::Pcm);
-        assert!(fmt.sample_rate == 8_000);
-        assert!(fmt.sample_format == SampleFormat::I16);
-        assert!(fmt.channels == Channels::Mono);
+        let mut wav =
+            Wav::new_with_mode(TestFile::from_bytes(MS_ADPCM), ParseMode::Strict).unwrap();
+        assert!(wav.sample_format() == SampleFormat::I16);
+        assert!(wav.total_frames() == 12);
+        assert!(wav.count_frames() == Ok(12));
+
+        // an odd buffer keeps a decoded sample for the next read
+        let mut samples = [0_i16; 12];
+        assert!(wav.read_samples(&mut samples[..5]) == Ok(5));
+        assert!(wav.played() == 5);
+        assert!(wav.read_samples(&mut samples[5..]) == Ok(7));
+        assert!(
+            samples
+                == [
+                    50, 100, 116, 148, 196, 180, -100, -200, -76, -408, -1956, -3048
+                ]
+        );
+        assert!(wav.is_eof());
+
+        // seeking snaps back to the start of the block
+        wav.restart().unwrap();
+        wav.try_seek(7).unwrap();
+        assert!(wav.played() == 6);
+        assert!(wav.read_samples(&mut samples[..1]) == Ok(1));
+        assert!(samples[0] == -100);
+    }
+
+    #[test]
+    fn chunk_data_len() {
+        let chunk = super::parse_chunk(b"abcd\x03\x00\x00\x00", 12);
+        assert!(chunk.padded);
+        assert!(chunk.end - chunk.start == 4);
+        assert!(chunk.data_len() == 3);
+
+        let chunk = super::parse_chunk(b"abcd\x04\x00\x00\x00", 12);
+        assert!(!chunk.padded);
+        assert!(chunk.data_len() == 4);
+    }
+
+    #[test]
+    fn display_title() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x3a, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x40, 0x1f, 0x00, 0x00, // byte rate
+            0x01, 0x00, // block align
+            0x08, 0x00, // bits per sample
+            0x44, 0x49, 0x53, 0x50, // DISP
+            0x0b, 0x00, 0x00, 0x00, // DISP chunk size
+            0x01, 0x00, 0x00, 0x00, // CF_TEXT
+            0x54, 0x72, 0x61, 0x63, 0x6b, 0x20, 0x31, // Track 1
+            0x00, // pad byte
+            0x64, 0x61, 0x74, 0x61, // data
+            0x02, 0x00, 0x00, 0x00, // data chunk size
+            0x80, 0x80, // samples
+        ]);
+        let wav = Wav::new(file).unwrap();
+        assert!(wav.display_title() == Some("Track 1"));
+
+        // anything but text is ignored
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x32, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x40, 0x1f, 0x00, 0x00, // byte rate
+            0x01, 0x00, // block align
+            0x08, 0x00, // bits per sample
+            0x44, 0x49, 0x53, 0x50, // DISP
+            0x04, 0x00, 0x00, 0x00, // DISP chunk size
+            0x08, 0x00, 0x00, 0x00, // CF_DIB
+            0x64, 0x61, 0x74, 0x61, // data
+            0x02, 0x00, 0x00, 0x00, // data chunk size
+            0x80, 0x80, // samples
+        ]);
+        let wav = Wav::new(file).unwrap();
+        assert!(wav.display_title().is_none());
+    }
+
+    #[test]
+    fn bext_umid() {
+        let mut bext = std::vec![0_u8; 602];
+        bext[338..346].copy_from_slice(&48_000_u64.to_le_bytes());
+        bext[346] = 2; // version
+        for (i, byte) in bext[348..412].iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        bext.extend_from_slice(b"A=PCM,F=8000,W=8,M=mono\r\n\0");
+
+        let mut bytes = std::vec::Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(4 + 24 + 8 + bext.len() as u32 + 10).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(&[
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x40, 0x1f, 0x00, 0x00, // byte rate
+            0x01, 0x00, // block align
+            0x08, 0x00, // bits per sample
+        ]);
+        bytes.extend_from_slice(b"bext");
+        bytes.extend_from_slice(&(bext.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&bext);
+        bytes.extend_from_slice(b"data\x02\x00\x00\x00\x80\x80");
+
+        let wav = Wav::new(SliceFile::new(&bytes)).unwrap();
+        let info = wav.bext().unwrap();
+        assert!(info.version == 2);
+        assert!(info.time_reference == 48_000);
+        assert!(info.umid.unwrap()[..] == (0..64).collect::<std::vec::Vec<u8>>()[..]);
+        assert!(info.coding_history == "A=PCM,F=8000,W=8,M=mono\r\n");
+
+        // version 0 chunks have no UMID
+        bytes[12 + 24 + 8 + 346] = 0;
+        let wav = Wav::new(SliceFile::new(&bytes)).unwrap();
+        assert!(wav.bext().unwrap().umid.is_none());
+    }
+
+    #[test]
+    fn loudness_estimate() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x2c, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x08, 0x00, 0x00, 0x00, // data chunk size
+            0x00, 0x40, // sample 1
+            0x00, 0xc0, // sample 2
+            0x00, 0x00, // sample 3
+            0x00, 0x00, // sample 4
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+        let mut sample = [0_u8; 2];
+        wav.read(&mut sample).unwrap();
+
+        // half scale is about -6 dBFS, the silent frames are gated out
+        let loudness = wav.loudness_estimate().unwrap();
+        assert!((-6.1..-5.9).contains(&loudness));
+        assert!(wav.played() == 1);
+        wav.read(&mut sample).unwrap();
+        assert!(sample == [0x00, 0xc0]);
+
+        assert!((super::log10(1000.0) - 3.0).abs() < 1e-4);
+        assert!((super::log10(0.02) + 1.69897).abs() < 1e-4);
+
+        let mut wav = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        assert!(wav.loudness_estimate().unwrap() > -1.0);
+    }
+
+    #[test]
+    fn from_raw_pcm() {
+        let file = TestFile::from_bytes(&[0x01, 0x00, 0xfe, 0xff, 0x02, 0x00]);
+        let mut wav = Wav::from_raw_pcm(file, 8000, Channels::Mono, SampleFormat::I16);
+        assert!(wav.total_frames() == 3);
+        assert!(wav.chunks().is_empty());
+
+        let mut samples = [0_i16; 4];
+        assert!(wav.read_samples(&mut samples) == Ok(3));
+        assert!(samples[..3] == [1, -2, 2]);
+        assert!(wav.is_eof());
+
+        wav.restart().unwrap();
+        assert!(wav.read_samples(&mut samples[..1]) == Ok(1));
+        assert!(samples[0] == 1);
     }
 
+    #[cfg(feature = "diagnostics")]
     #[test]
-    fn parse_le_16bit_8k_mono() {
+    fn short_read_counters() {
+        let file = FlakyFile {
+            file: TestFile::from_bytes(&[
+                0x52, 0x49, 0x46, 0x46, // RIFF
+                0x28, 0x00, 0x00, 0x00, // chunk size
+                0x57, 0x41, 0x56, 0x45, // WAVE
+                0x66, 0x6d, 0x74, 0x20, // fmt
+                0x10, 0x00, 0x00, 0x00, // fmt chunk size
+                0x01, 0x00, // audio format
+                0x01, 0x00, // channel count
+                0x40, 0x1f, 0x00, 0x00, // sample rate
+                0x40, 0x1f, 0x00, 0x00, // byte rate
+                0x01, 0x00, // block align
+                0x08, 0x00, // bits per sample
+                0x64, 0x61, 0x74, 0x61, // data
+                0x04, 0x00, 0x00, 0x00, // data chunk size
+                0x10, 0x20, 0x30, 0x40, // samples
+            ]),
+            flaky: false,
+            fail: false,
+        };
+        let mut wav = Wav::new_streaming(file).unwrap();
+        wav.get_mut().flaky = true;
+
+        let mut samples = [0_u8; 4];
+        assert!(wav.read(&mut samples) == Ok(1));
+        assert!(wav.read(&mut samples) == Ok(1));
+        assert!(wav.short_reads() == 2);
+
+        // the final sample ends the data, so it isn't short
+        assert!(wav.read(&mut samples[..2]) == Ok(1));
+        assert!(wav.read(&mut samples) == Ok(1));
+        assert!(wav.is_eof());
+        assert!(wav.short_reads() == 3);
+        assert!(wav.total_reads() == 4);
+    }
+
+    #[test]
+    fn retry_io_errors() {
+        let file = FailingFile {
+            file: TestFile::from_bytes(MONO_16BIT),
+            failures: 0,
+        };
+        let mut wav = Wav::new(file).unwrap();
+        let mut samples = [0_u8; 8];
+
+        wav.get_mut().failures = 1;
+        assert!(wav.read(&mut samples) == Err(Error::PlatformError(PlatformFileError::Io)));
+
+        wav.set_retry(2);
+        wav.get_mut().failures = 2;
+        assert!(wav.read(&mut samples) == Ok(8));
+        assert!(samples == MONO_16BIT[44..]);
+
+        // fails part way through the buffer, retried from where it stopped
+        let file = TornReadFile {
+            file: TestFile::from_bytes(MONO_16BIT),
+            torn: 0,
+            fail: false,
+        };
+        let mut wav = Wav::new(file).unwrap();
+        wav.set_retry(3);
+        wav.get_mut().torn = 3;
+        assert!(wav.read(&mut samples) == Ok(8));
+        assert!(samples == MONO_16BIT[44..]);
+    }
+
+    #[test]
+    fn align_to_frame() {
         let file = TestFile::from_bytes(&[
             0x52, 0x49, 0x46, 0x46, // RIFF
-            0x32, 0x00, 0x00, 0x00, // chunk size
+            0x2c, 0x00, 0x00, 0x00, // chunk size
             0x57, 0x41, 0x56, 0x45, // WAVE
             0x66, 0x6d, 0x74, 0x20, // fmt
             0x10, 0x00, 0x00, 0x00, // fmt chunk size
             0x01, 0x00, // audio format
-            0x01, 0x00, // channel count
+            0x02, 0x00, // channel count
             0x40, 0x1f, 0x00, 0x00, // sample rate
             0x80, 0x3e, 0x00, 0x00, // byte rate
-            0x20, 0x00, // block align
-            0x10, 0x00, // bits per sample
+            0x02, 0x00, // block align
+            0x08, 0x00, // bits per sample
             0x64, 0x61, 0x74, 0x61, // data
             0x08, 0x00, 0x00, 0x00, // data chunk size
-            0x01, 0x00, // sample 1
-            0xfe, 0xff, // sample 2
-            0x02, 0x00, // sample 3
-            0xff, 0xff, // sample 4
+            0x01, 0x00, // sample 1 L+R
+            0xfe, 0xff, // sample 2 L+R
+            0x02, 0x00, // sample 3 L+R
+            0xff, 0xff, // sample 4 L+R
         ]);
-        let mut wav = Wav::new(file).unwrap();
+        let mut wav = Wav::new_with_mode(file, ParseMode::Strict).unwrap();
+        wav.sync_position(3).unwrap();
+        let mut frame = [0_u8; 2];
+        assert!(wav.read(&mut frame) == Err(Error::MisalignedPosition));
 
-        assert!(wav.fmt.channels == Channels::Mono);
-        assert!(wav.fmt.sample_rate == 8_000);
-        assert!(wav.fmt.sample_format == SampleFormat::I16);
+        wav.align_to_frame().unwrap();
+        assert!(wav.played() == 4);
+        assert!(wav.read(&mut frame) == Ok(2));
+        assert!(frame == [0x02, 0x00]);
 
-        let mut sample = [0_u8; 2]; // size of one sample
-        wav.read(&mut sample).unwrap();
-        assert!(sample == [0x01, 0x00]);
-        wav.read(&mut sample).unwrap();
-        assert!(sample == [0xfe, 0xff]);
-        wav.read(&mut sample).unwrap();
-        assert!(sample == [0x02, 0x00]);
-        wav.read(&mut sample).unwrap();
-        assert!(sample == [0xff, 0xff]);
+        // already aligned
+        wav.align_to_frame().unwrap();
+        assert!(wav.played() == 6);
     }
 
     #[test]
-    fn parse_le_8bit_8k_stereo() {
+    fn strict_short_buffer() {
         let file = TestFile::from_bytes(&[
             0x52, 0x49, 0x46, 0x46, // RIFF
-            0x32, 0x00, 0x00, 0x00, // chunk size
+            0x2c, 0x00, 0x00, 0x00, // chunk size
             0x57, 0x41, 0x56, 0x45, // WAVE
             0x66, 0x6d, 0x74, 0x20, // fmt
             0x10, 0x00, 0x00, 0x00, // fmt chunk size
@@ -379,7 +5014,7 @@ mod tests {
             0x02, 0x00, // channel count
             0x40, 0x1f, 0x00, 0x00, // sample rate
             0x80, 0x3e, 0x00, 0x00, // byte rate
-            0x20, 0x00, // block align
+            0x02, 0x00, // block align
             0x08, 0x00, // bits per sample
             0x64, 0x61, 0x74, 0x61, // data
             0x08, 0x00, 0x00, 0x00, // data chunk size
@@ -388,62 +5023,463 @@ mod tests {
             0x02, 0x00, // sample 3 L+R
             0xff, 0xff, // sample 4 L+R
         ]);
-        let mut wav = Wav::new(file).unwrap();
+        let mut wav = Wav::new_with_mode(file, ParseMode::Strict).unwrap();
 
-        assert!(wav.fmt.channels == Channels::Stereo);
-        assert!(wav.fmt.sample_rate == 8_000);
-        assert!(wav.fmt.sample_format == SampleFormat::U8);
+        // a buffer too short for a frame leaves the position on the frame boundary
+        let mut byte = [0_u8; 1];
+        for _ in 0..3 {
+            assert!(wav.read(&mut byte) == Ok(0));
+        }
+        assert!(wav.position_in_data() == 0);
 
-        let mut sample = [0_u8; 2]; // size of one sample L+R
-        wav.read(&mut sample).unwrap(); // sample 1
-        assert!(sample == [0x01, 0x00]);
-        wav.read(&mut sample).unwrap(); // sample 2
-        assert!(sample == [0xfe, 0xff]);
-        wav.read(&mut sample).unwrap(); // sample 3
-        assert!(sample == [0x02, 0x00]);
-        wav.read(&mut sample).unwrap(); // sample 4
-        assert!(sample == [0xff, 0xff]);
+        let mut frame = [0_u8; 2];
+        assert!(wav.read(&mut frame) == Ok(2));
+        assert!(frame == [0x01, 0x00]);
+        assert!(wav.read(&mut byte) == Ok(0));
+        assert!(wav.read(&mut frame) == Ok(2));
+        assert!(frame == [0xfe, 0xff]);
+    }
 
-        assert!(wav.read(&mut sample) == Ok(0));
+    #[test]
+    fn new_scan() {
+        const JUNK_PREFIX: &[u8] = &[
+            0x00, 0x0d, 0x0a, // junk
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x2c, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, // sample 1
+            0xfe, 0xff, // sample 2
+        ];
+        assert!(matches!(
+            Wav::new(TestFile::from_bytes(JUNK_PREFIX)),
+            Err(Error::NoRiffChunkFound)
+        ));
+
+        let mut wav = Wav::new_scan(TestFile::from_bytes(JUNK_PREFIX)).unwrap();
+        assert!(wav.get_ref().base() == 3);
+        let mut samples = [0_i16; 2];
+        assert!(wav.read_samples(&mut samples) == Ok(2));
+        assert!(samples == [1, -2]);
+
+        // a file without a header anywhere
+        assert!(matches!(
+            Wav::new_scan(TestFile::from_bytes(&[0x52; 200])),
+            Err(Error::NoRiffChunkFound)
+        ));
     }
 
     #[test]
-    fn chunk_after_data() {
+    fn valid_bits() {
         let file = TestFile::from_bytes(&[
             0x52, 0x49, 0x46, 0x46, // RIFF
-            0x32, 0x00, 0x00, 0x00, // chunk size
+            0x44, 0x00, 0x00, 0x00, // chunk size
             0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x28, 0x00, 0x00, 0x00, // fmt chunk size
+            0xfe, 0xff, // audio format (extensible)
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x00, 0x7d, 0x00, 0x00, // byte rate
+            0x04, 0x00, // block align
+            0x20, 0x00, // bits per sample
+            0x16, 0x00, // extension size
+            0x18, 0x00, // valid bits per sample
+            0x04, 0x00, 0x00, 0x00, // channel mask
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, // sub format (pcm)
+            0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71, // sub format guid
             0x64, 0x61, 0x74, 0x61, // data
             0x08, 0x00, 0x00, 0x00, // data chunk size
-            0x01, 0x00, // sample 1 L+R
-            0xfe, 0xff, // sample 2 L+R
-            0x02, 0x00, // sample 3 L+R
-            0xff, 0xff, // sample 4 L+R
+            0xff, 0x00, 0x00, 0x40, // sample 1, padding bits set
+            0x00, 0x01, 0x00, 0xc0, // sample 2
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+        assert!(wav.sample_format() == SampleFormat::I32);
+        assert!(wav.valid_bits() == Some(24));
+
+        // the low byte below the 24 valid bits is cleared
+        let mut samples = [0_i32; 2];
+        assert!(wav.read_samples(&mut samples) == Ok(2));
+        assert!(samples == [0x4000_0000, 0xc000_0100_u32 as i32]);
+
+        let pcm = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(pcm.valid_bits() == Some(16));
+    }
+
+    #[test]
+    fn read_frames_packed() {
+        let mut wav = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        let mut words = [0_u32; 4];
+        assert!(wav.read_frames_packed(&mut words[..2], PackOrder::LeftHigh) == Ok(2));
+        assert!(words[..2] == [0x8100_8000, 0x7e00_7f00]);
+        assert!(wav.read_frames_packed(&mut words, PackOrder::RightHigh) == Ok(2));
+        assert!(words[..2] == [0x8000_8200, 0x7f00_7f00]);
+
+        let mut mono = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(
+            mono.read_frames_packed(&mut words, PackOrder::LeftHigh)
+                == Err(Error::ChannelCountMismatch)
+        );
+    }
+
+    #[test]
+    fn empty_data_chunk() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x30, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
             0x66, 0x6d, 0x74, 0x20, // fmt
             0x10, 0x00, 0x00, 0x00, // fmt chunk size
             0x01, 0x00, // audio format
-            0x02, 0x00, // channel count
+            0x01, 0x00, // channel count
             0x40, 0x1f, 0x00, 0x00, // sample rate
             0x80, 0x3e, 0x00, 0x00, // byte rate
-            0x20, 0x00, // block align
-            0x08, 0x00, // bits per sample
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x00, 0x00, 0x00, 0x00, // data chunk size
+            0x61, 0x62, 0x63, 0x64, // abcd
+            0x00, 0x00, 0x00, 0x00, // abcd chunk size
         ]);
         let mut wav = Wav::new(file).unwrap();
+        assert!(wav.is_eof());
+        assert!(wav.total_frames() == 0);
 
-        assert!(wav.fmt.channels == Channels::Stereo);
-        assert!(wav.fmt.sample_rate == 8_000);
-        assert!(wav.fmt.sample_format == SampleFormat::U8);
+        let pos = wav.get_ref().current_pos;
+        let mut samples = [0_u8; 4];
+        assert!(wav.read(&mut samples) == Ok(0));
+        assert!(wav.get_ref().current_pos == pos);
 
-        let mut sample = [0_u8; 2]; // size of one sample L+R
-        wav.read(&mut sample).unwrap(); // sample 1
-        assert!(sample == [0x01, 0x00]);
-        wav.read(&mut sample).unwrap(); // sample 2
+        wav.set_eof_mode(EofMode::Loop);
+        assert!(wav.read(&mut samples) == Ok(0));
+    }
+
+    #[test]
+    fn overhead_bytes() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(wav.audio_bytes() == 8);
+        assert!(wav.overhead_bytes() == MONO_16BIT.len() as u64 - 8);
+    }
+
+    #[test]
+    fn refresh_length() {
+        static GROWN: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0xff, 0xff, 0xff, 0xff, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x00, 0xff, 0xff, 0x7f, // data chunk size, not known yet
+            0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04, 0x00, // samples
+        ];
+        // the recorder has written two samples so far
+        let mut wav = Wav::new(TestFile::from_bytes(&GROWN[..48])).unwrap();
+        let mut samples = [0_u8; 8];
+        assert!(wav.read(&mut samples) == Ok(4));
+        assert!(wav.read(&mut samples) == Ok(0));
+
+        // half of the next frame isn't readable yet
+        wav.file.contents = &GROWN[..51];
+        wav.refresh_length().unwrap();
+        assert!(wav.data_len() == 6);
+
+        wav.file.contents = GROWN;
+        wav.refresh_length().unwrap();
+        assert!(wav.data_len() == 8);
+        assert!(wav.read(&mut samples) == Ok(4));
+        assert!(samples[..4] == [0x03, 0x00, 0x04, 0x00]);
+    }
+
+    #[test]
+    fn uppercase_tags() {
+        static BYTES: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x28, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x46, 0x4d, 0x54, 0x20, // FMT
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x44, 0x41, 0x54, 0x41, // DATA
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, 0xfe, 0xff, // samples
+        ];
+        assert!(matches!(
+            Wav::new(TestFile::from_bytes(BYTES)),
+            Err(Error::NoFmtChunkFound)
+        ));
+        assert!(matches!(
+            Wav::new_with_mode(TestFile::from_bytes(BYTES), ParseMode::Strict),
+            Err(Error::NoFmtChunkFound)
+        ));
+
+        let mut wav = Wav::new_with_mode(TestFile::from_bytes(BYTES), ParseMode::Lenient).unwrap();
+        let mut samples = [0_i16; 2];
+        assert!(wav.read_samples(&mut samples) == Ok(2));
+        assert!(samples == [1, -2]);
+    }
+
+    #[test]
+    fn peek_frame_bytes() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        let mut frame = [0_u8; 2];
+        assert!(wav.peek_frame_bytes(&mut frame) == Ok(2));
+        assert!(frame == MONO_16BIT[44..46]);
+        assert!(wav.played() == 0);
+
+        let mut samples = [0_u8; 8];
+        assert!(wav.read(&mut samples) == Ok(8));
+        assert!(samples == MONO_16BIT[44..]);
+        assert!(wav.peek_frame_bytes(&mut frame) == Ok(0));
+    }
+
+    #[test]
+    fn write_header() {
+        for bytes in [MONO_16BIT, STEREO_8BIT] {
+            let wav = Wav::new(TestFile::from_bytes(bytes)).unwrap();
+            let mut header = [0_u8; 44];
+            assert!(wav.write_header(&mut header) == Ok(44));
+            assert!(
+                Wav::<TestFile>::from_header_bytes(&header)
+                    == Wav::<TestFile>::from_header_bytes(bytes)
+            );
+        }
+
+        // a canonical extensible header comes back byte for byte
+        static EXTENSIBLE: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x44, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x28, 0x00, 0x00, 0x00, // fmt chunk size
+            0xfe, 0xff, // audio format (extensible)
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x00, 0x7d, 0x00, 0x00, // byte rate
+            0x04, 0x00, // block align
+            0x20, 0x00, // bits per sample
+            0x16, 0x00, // extension size
+            0x18, 0x00, // valid bits per sample
+            0x04, 0x00, 0x00, 0x00, // channel mask
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, // sub format (pcm)
+            0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71, // sub format guid
+            0x64, 0x61, 0x74, 0x61, // data
+            0x08, 0x00, 0x00, 0x00, // data chunk size
+            0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0xc0, // samples
+        ];
+        let wav = Wav::new(TestFile::from_bytes(EXTENSIBLE)).unwrap();
+        let mut header = [0_u8; 80];
+        assert!(wav.write_header(&mut header) == Ok(68));
+        assert!(header[..68] == EXTENSIBLE[..68]);
+
+        assert!(wav.write_header(&mut header[..60]) == Err(Error::ChunkSizeIncorrect));
+    }
+
+    #[test]
+    fn missing_pad_byte() {
+        static BYTES: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x29, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x11, 0x00, 0x00, 0x00, // fmt chunk size, odd with no pad byte after it
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x00, // stray byte
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, 0xfe, 0xff, // samples
+        ];
+        assert!(matches!(
+            Wav::new(TestFile::from_bytes(BYTES)),
+            Err(Error::NoDataChunkFound)
+        ));
+
+        let mut wav = Wav::new_with_mode(TestFile::from_bytes(BYTES), ParseMode::Lenient).unwrap();
+        assert!(wav.data_start_offset() == 45);
+        let mut samples = [0_i16; 2];
+        assert!(wav.read_samples(&mut samples) == Ok(2));
+        assert!(samples == [1, -2]);
+    }
+
+    #[test]
+    fn data_start_offset() {
+        // RIFF header, a 16 byte fmt chunk and the data chunk header
+        let wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        assert!(wav.data_start_offset() == 12 + 24 + 8);
+    }
+
+    #[test]
+    fn read_f64() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x34, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x03, 0x00, // audio format (float)
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x00, 0xfa, 0x00, 0x00, // byte rate
+            0x08, 0x00, // block align
+            0x40, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x10, 0x00, 0x00, 0x00, // data chunk size
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xe0, 0x3f, // 0.5
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xd0, 0xbf, // -0.25
+        ]);
+        let mut wav = Wav::new_with_mode(file, ParseMode::Strict).unwrap();
+        assert!(wav.sample_format() == SampleFormat::F64);
+        assert!(wav.total_frames() == 2);
+
+        let mut samples = [0_f32; 2];
+        assert!(wav.read_samples(&mut samples) == Ok(2));
+        assert!(samples == [0.5, -0.25]);
+
+        wav.restart().unwrap();
+        wav.set_output_format(SampleFormat::I16);
+        let mut samples = [0_i16; 2];
+        assert!(wav.read_samples(&mut samples) == Ok(2));
+        assert!(samples == [16384, -8192]);
+    }
+
+    #[test]
+    fn frames() {
+        let mut wav = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        let mut frames = wav.frames();
+        for expected in [
+            [-32512, -32768],
+            [32256, 32512],
+            [-32256, -32768],
+            [32512, 32512],
+        ] {
+            let frame = frames.next().unwrap().unwrap();
+            assert!(frame.len() == 2);
+            assert!(frame[..] == expected);
+        }
+        assert!(frames.next().is_none());
+        assert!(wav.is_eof());
+    }
+
+    #[test]
+    fn data_crc32() {
+        let mut wav = Wav::new(TestFile::from_bytes(MONO_16BIT)).unwrap();
+        let mut sample = [0_u8; 2];
+        wav.read(&mut sample).unwrap();
+
+        assert!(wav.data_crc32() == Ok(0x585b_ba66));
+        // the read position is unchanged
+        wav.read(&mut sample).unwrap();
         assert!(sample == [0xfe, 0xff]);
-        wav.read(&mut sample).unwrap(); // sample 3
-        assert!(sample == [0x02, 0x00]);
-        wav.read(&mut sample).unwrap(); // sample 4
-        assert!(sample == [0xff, 0xff]);
+    }
 
-        assert!(wav.read(&mut sample) == Ok(0));
+    #[test]
+    fn clip_count() {
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x2e, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0x01, 0x00, // audio format
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x0a, 0x00, 0x00, 0x00, // data chunk size
+            0xff, 0x7f, // full scale
+            0xfe, 0x7f, // sample 2
+            0x00, 0x80, // negative full scale
+            0x01, 0x80, // sample 4
+            0xff, 0x7f, // full scale
+        ]);
+        let mut wav = Wav::new(file).unwrap();
+        let mut sample = [0_u8; 2];
+        wav.read(&mut sample).unwrap();
+
+        assert!(wav.clip_count() == Ok(3));
+        assert!(wav.played() == 1);
+
+        // 8 bit samples clip at 0 and 255
+        let mut wav = Wav::new(TestFile::from_bytes(STEREO_8BIT)).unwrap();
+        assert!(wav.clip_count() == Ok(5));
+    }
+
+    #[test]
+    fn fmt_read_by_length() {
+        // the extensible fmt chunk runs past the first 64 bytes of the file
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x5c, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x4a, 0x55, 0x4e, 0x4b, // JUNK
+            0x14, 0x00, 0x00, 0x00, // JUNK chunk size
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // padding
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x28, 0x00, 0x00, 0x00, // fmt chunk size
+            0xfe, 0xff, // audio format (extensible)
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x16, 0x00, // extension size
+            0x10, 0x00, // valid bits per sample
+            0x04, 0x00, 0x00, 0x00, // channel mask
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, // sub format (pcm)
+            0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71, // sub format guid
+            0x64, 0x61, 0x74, 0x61, // data
+            0x04, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, 0xfe, 0xff, // samples
+        ]);
+        let wav = Wav::new_with_mode(file, ParseMode::Strict).unwrap();
+        assert!(wav.speaker_layout() == Some(SpeakerLayout::FRONT_CENTER));
+        assert!(wav.total_frames() == 2);
+
+        // a 16 byte extensible fmt chunk can't borrow its extension from the next chunk
+        let file = TestFile::from_bytes(&[
+            0x52, 0x49, 0x46, 0x46, // RIFF
+            0x2c, 0x00, 0x00, 0x00, // chunk size
+            0x57, 0x41, 0x56, 0x45, // WAVE
+            0x66, 0x6d, 0x74, 0x20, // fmt
+            0x10, 0x00, 0x00, 0x00, // fmt chunk size
+            0xfe, 0xff, // audio format (extensible)
+            0x01, 0x00, // channel count
+            0x40, 0x1f, 0x00, 0x00, // sample rate
+            0x80, 0x3e, 0x00, 0x00, // byte rate
+            0x02, 0x00, // block align
+            0x10, 0x00, // bits per sample
+            0x64, 0x61, 0x74, 0x61, // data
+            0x08, 0x00, 0x00, 0x00, // data chunk size
+            0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, // samples
+        ]);
+        assert!(matches!(Wav::new(file), Err(Error::FmtChunkError)));
     }
 }
@@ -0,0 +1,54 @@
+use heapless::String;
+
+use super::{Chunk, Error, cue::to_label, read_at};
+use crate::PlatformFile;
+
+/// Longest coding history kept from a `bext` chunk, longer ones are truncated
+pub const MAX_CODING_HISTORY_LEN: usize = 256;
+
+/// Offset of the time reference, the fields before it are fixed length text
+const TIME_REFERENCE_OFFSET: u64 = 338;
+/// Offset of the coding history, after the reserved bytes
+const CODING_HISTORY_OFFSET: usize = 602;
+
+/// Broadcast extension from a `bext` chunk
+#[derive(PartialEq, Clone, Debug)]
+pub struct BextInfo {
+    pub version: u16,
+    /// first sample of the file counted in samples since midnight
+    pub time_reference: u64,
+    /// SMPTE unique material identifier, `None` for version 0 chunks which predate it
+    pub umid: Option<[u8; 64]>,
+    /// lines of `A=PCM,F=48000,...` describing how the audio was processed
+    pub coding_history: String<MAX_CODING_HISTORY_LEN>,
+}
+
+/// parse a `bext` chunk, the coding history is whatever follows the fixed fields
+pub(super) fn parse_bext<File: PlatformFile>(
+    file: &mut File,
+    chunk: &Chunk,
+) -> Result<BextInfo, Error> {
+    // time reference, version and UMID
+    let mut fields = [0_u8; 74];
+    if chunk.data_len() < CODING_HISTORY_OFFSET
+        || read_at(file, chunk.start + TIME_REFERENCE_OFFSET, &mut fields)? < fields.len()
+    {
+        return Err(Error::ChunkSizeIncorrect);
+    }
+    let version = u16::from_le_bytes([fields[8], fields[9]]);
+
+    let mut history = [0_u8; MAX_CODING_HISTORY_LEN];
+    let history_len = (chunk.data_len() - CODING_HISTORY_OFFSET).min(history.len());
+    let read = read_at(
+        file,
+        chunk.start + CODING_HISTORY_OFFSET as u64,
+        &mut history[..history_len],
+    )?;
+
+    Ok(BextInfo {
+        version,
+        time_reference: u64::from_le_bytes(fields[..8].try_into().unwrap()),
+        umid: (version >= 1).then(|| fields[10..].try_into().unwrap()),
+        coding_history: to_label(&history[..read]),
+    })
+}
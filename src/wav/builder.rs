@@ -0,0 +1,169 @@
+use super::{EofMode, Error, ParseMode, SeekPolicy, Wav};
+use crate::{Endian, PlatformFile, Q15, SampleFormat};
+
+/// Collects the read time options of a [`Wav`] and applies them when parsing a file
+///
+/// files that can't seek are parsed with [`Wav::new_streaming`], every other file with
+/// [`Wav::new_with_mode`]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct WavBuilder {
+    mode: ParseMode,
+    output_format: Option<SampleFormat>,
+    sample_endian: Option<Endian>,
+    gain: Option<Q15>,
+    dither: bool,
+    eof_mode: EofMode,
+    seek_policy: SeekPolicy,
+}
+
+impl WavBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// see [`Wav::new_with_mode`]
+    pub fn parse_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// see [`Wav::set_output_format`]
+    pub fn output_format(mut self, format: SampleFormat) -> Self {
+        self.output_format = Some(format);
+        self
+    }
+
+    /// see [`Wav::with_sample_endian`]
+    pub fn sample_endian(mut self, endian: Endian) -> Self {
+        self.sample_endian = Some(endian);
+        self
+    }
+
+    /// see [`Wav::set_gain`]
+    pub fn gain(mut self, gain: Q15) -> Self {
+        self.gain = Some(gain);
+        self
+    }
+
+    /// see [`Wav::set_dither`]
+    pub fn dither(mut self, enabled: bool) -> Self {
+        self.dither = enabled;
+        self
+    }
+
+    /// see [`Wav::set_eof_mode`]
+    pub fn eof_mode(mut self, mode: EofMode) -> Self {
+        self.eof_mode = mode;
+        self
+    }
+
+    /// see [`Wav::set_seek_policy`]
+    pub fn seek_policy(mut self, policy: SeekPolicy) -> Self {
+        self.seek_policy = policy;
+        self
+    }
+
+    /// parse `file` and apply the options
+    ///
+    /// returns [`Error::SeekUnsupported`] if [`EofMode::Loop`] is set for a file that can't
+    /// seek back to its first sample. Returns [`Error::FormatMismatch`] if dither is on
+    /// but the output format isn't an integer format narrower than the file's, as there's
+    /// nothing to dither, or if a gain is set with a float output format, as the gain is
+    /// applied in fixed point and would clip float samples outside full scale. Every other
+    /// combination is accepted, the sample endian and EOF and seek policies don't affect
+    /// each other
+    pub fn build<File: PlatformFile>(self, file: File) -> Result<Wav<File>, Error> {
+        let mut wav = match file.can_seek() {
+            true => Wav::new_with_mode(file, self.mode)?,
+            false if self.eof_mode == EofMode::Loop => return Err(Error::SeekUnsupported),
+            false => Wav::new_streaming(file)?,
+        };
+
+        let source = wav.fmt.sample_format;
+        let output = self.output_format.unwrap_or(source);
+        // 32 bit outputs hold every bit of the widened samples
+        if self.dither && !(output.size() < source.size() && output.size() < 4) {
+            return Err(Error::FormatMismatch);
+        }
+        if self.gain.is_some() && matches!(output, SampleFormat::F32 | SampleFormat::F64) {
+            return Err(Error::FormatMismatch);
+        }
+
+        if let Some(format) = self.output_format {
+            wav.set_output_format(format);
+        }
+        if let Some(endian) = self.sample_endian {
+            wav = wav.with_sample_endian(endian);
+        }
+        if let Some(gain) = self.gain {
+            wav.set_gain(gain);
+        }
+        wav.set_dither(self.dither);
+        wav.set_eof_mode(self.eof_mode);
+        wav.set_seek_policy(self.seek_policy);
+        Ok(wav)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WavBuilder;
+    use crate::{
+        AudioFile, MONO_16BIT, Q15, STEREO_8BIT, SampleFormat, TestFile,
+        test_files::StreamFile,
+        wav::{EofMode, Error},
+    };
+
+    #[test]
+    fn gain_and_output_format() {
+        let mut wav = WavBuilder::new()
+            .gain(Q15(0x4000))
+            .output_format(SampleFormat::I16)
+            .build(TestFile::from_bytes(STEREO_8BIT))
+            .unwrap();
+        assert!(wav.sample_format() == SampleFormat::I16);
+
+        // 8 bit samples widened to 16 bit then halved
+        let mut samples = [0_i16; 4];
+        assert!(wav.read_samples(&mut samples) == Ok(4));
+        assert!(samples == [-16256, -16384, 16128, 16256]);
+    }
+
+    #[test]
+    fn loop_needs_seek() {
+        let built = WavBuilder::new()
+            .eof_mode(EofMode::Loop)
+            .build(StreamFile(TestFile::from_bytes(MONO_16BIT)));
+        assert!(matches!(built, Err(Error::SeekUnsupported)));
+
+        // streams can still be built without looping
+        let wav = WavBuilder::new()
+            .build(StreamFile(TestFile::from_bytes(MONO_16BIT)))
+            .unwrap();
+        assert!(!wav.is_seekable());
+    }
+
+    #[test]
+    fn rejects_conflicting_options() {
+        // nothing to dither when the samples are widened or kept as they are
+        for output in [None, Some(SampleFormat::I16)] {
+            let mut builder = WavBuilder::new().dither(true);
+            if let Some(output) = output {
+                builder = builder.output_format(output);
+            }
+            let built = builder.build(TestFile::from_bytes(STEREO_8BIT));
+            assert!(matches!(built, Err(Error::FormatMismatch)));
+        }
+        let built = WavBuilder::new()
+            .dither(true)
+            .output_format(SampleFormat::U8)
+            .build(TestFile::from_bytes(MONO_16BIT));
+        assert!(built.is_ok());
+
+        let built = WavBuilder::new()
+            .gain(Q15(0x4000))
+            .output_format(SampleFormat::F32)
+            .build(TestFile::from_bytes(STEREO_8BIT));
+        assert!(matches!(built, Err(Error::FormatMismatch)));
+    }
+}
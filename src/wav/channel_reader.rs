@@ -0,0 +1,63 @@
+use super::{Error, Wav};
+use crate::{AudioFile, Channels, PlatformFile, SampleFormat};
+
+/// One channel of a [`Wav`] with any number of channels played as a mono source, see
+/// [`Wav::channel`]
+///
+/// reads carry on from the wav's position, a few frames at a time through a small buffer so
+/// nothing channel sized is held
+pub struct ChannelReader<'a, File: PlatformFile> {
+    wav: &'a mut Wav<File>,
+    channel: usize,
+}
+
+impl<'a, File: PlatformFile> ChannelReader<'a, File> {
+    pub(super) fn new(wav: &'a mut Wav<File>, channel: usize) -> Self {
+        Self { wav, channel }
+    }
+
+    fn channel_count(&self) -> usize {
+        self.wav.channels().count() as usize
+    }
+}
+
+impl<File: PlatformFile> AudioFile<File> for ChannelReader<'_, File> {
+    type Error = Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.wav.read_channel(self.channel, buf)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.wav.sample_rate()
+    }
+
+    fn channels(&self) -> Channels {
+        Channels::Mono
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.wav.sample_format()
+    }
+
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Error> {
+        self.wav
+            .try_seek(sample_offset * self.channel_count() as i64)
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.wav.is_seekable()
+    }
+
+    fn played(&self) -> usize {
+        self.wav.played() / self.channel_count()
+    }
+
+    fn data_len(&self) -> usize {
+        self.wav.data_len() / self.channel_count()
+    }
+
+    fn is_eof(&self) -> bool {
+        self.wav.is_eof()
+    }
+}
@@ -0,0 +1,80 @@
+use core::cell::RefCell;
+
+use super::{Error, Wav};
+use crate::{AudioFile, Channels, PlatformFile, SampleFormat};
+
+/// One channel of a shared stereo [`Wav`] played as a mono source, see [`Wav::split_channels`]
+///
+/// each view keeps its own position and seeks the file before reading, so the views don't
+/// have to be read in lockstep
+pub struct ChannelView<'a, File: PlatformFile> {
+    wav: &'a RefCell<Wav<File>>,
+    channel: usize,
+    /// next frame to read
+    frame: usize,
+}
+
+impl<'a, File: PlatformFile> ChannelView<'a, File> {
+    pub(super) fn new(wav: &'a RefCell<Wav<File>>, channel: usize) -> Self {
+        Self {
+            wav,
+            channel,
+            frame: 0,
+        }
+    }
+}
+
+impl<File: PlatformFile> AudioFile<File> for ChannelView<'_, File> {
+    type Error = Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut wav = self.wav.borrow_mut();
+        let channels = wav.channels().count() as usize;
+        let sample_size = wav.sample_format().size() as usize;
+
+        let offset = (self.frame * channels) as i64 - wav.played() as i64;
+        wav.try_seek(offset)?;
+
+        let read = wav.read_channel(self.channel, buf)?;
+        self.frame += read / sample_size;
+        Ok(read)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.wav.borrow().sample_rate()
+    }
+
+    fn channels(&self) -> Channels {
+        Channels::Mono
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.wav.borrow().sample_format()
+    }
+
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Error> {
+        let target = self.frame as i64 + sample_offset;
+        if target < 0 || target > self.total_frames() as i64 {
+            return Err(Error::PositionOutOfBounds);
+        }
+        self.frame = target as usize;
+        Ok(())
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.wav.borrow().is_seekable()
+    }
+
+    fn played(&self) -> usize {
+        self.frame
+    }
+
+    fn data_len(&self) -> usize {
+        let wav = self.wav.borrow();
+        wav.data_len() / wav.channels().count() as usize
+    }
+
+    fn is_eof(&self) -> bool {
+        self.frame >= self.total_frames()
+    }
+}
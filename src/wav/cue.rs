@@ -0,0 +1,128 @@
+use heapless::{String, Vec};
+
+use super::{Chunk, Error, read_at};
+use crate::PlatformFile;
+
+/// Maximum number of cue points kept from the `cue ` chunk
+pub const MAX_CUE_POINTS: usize = 16;
+/// Maximum length in bytes of a cue label, longer labels are truncated
+pub const MAX_LABEL_LEN: usize = 32;
+
+/// A marked position in the audio data from the `cue ` chunk
+///
+/// the entry stores the position in several fields. For PCM `chunk_start` and `block_start`
+/// are byte offsets, of the data chunk the cue is in and of the block the cue is in, and
+/// `sample_offset` counts frames from the start of that data chunk, so `frame` is found from
+/// `chunk_start` and `sample_offset`. `block_start` only matters to compressed formats and
+/// is kept as is
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct CuePoint {
+    /// id used by labels to reference the cue point
+    pub id: u32,
+    /// byte offset of the header of the data chunk holding the cue point, from the first
+    /// data chunk. 0 unless the samples are split across a `wavl` list
+    pub chunk_start: u32,
+    /// byte offset of the block holding the cue point within its data chunk
+    pub block_start: u32,
+    /// frame of the cue point within its data chunk
+    pub sample_offset: u32,
+    /// frame of the cue point from the start of the audio, counting the frames of any
+    /// earlier data chunks and silence
+    pub frame: usize,
+}
+
+/// Text from a `labl` chunk in a `LIST`/`adtl` chunk
+pub(super) struct CueLabel {
+    pub(super) id: u32,
+    pub(super) text: String<MAX_LABEL_LEN>,
+}
+
+/// parse the cue points of a `cue ` chunk, any past [`MAX_CUE_POINTS`] are dropped
+pub(super) fn parse_cue<File: PlatformFile>(
+    file: &mut File,
+    chunk: &Chunk,
+) -> Result<Vec<CuePoint, MAX_CUE_POINTS>, Error> {
+    let mut cue_points = Vec::new();
+
+    let mut count = [0_u8; 4];
+    if read_at(file, chunk.start, &mut count)? < count.len() {
+        return Err(Error::ChunkSizeIncorrect);
+    }
+    let count = u32::from_le_bytes(count) as usize;
+
+    let mut entry = [0_u8; 24];
+    for offset in (chunk.start + 4..chunk.end)
+        .step_by(entry.len())
+        .take(count.min(MAX_CUE_POINTS))
+    {
+        if read_at(file, offset, &mut entry)? < entry.len() {
+            break;
+        }
+        cue_points
+            .push(CuePoint {
+                id: u32::from_le_bytes(entry[0..4].try_into().unwrap()),
+                chunk_start: u32::from_le_bytes(entry[12..16].try_into().unwrap()),
+                block_start: u32::from_le_bytes(entry[16..20].try_into().unwrap()),
+                sample_offset: u32::from_le_bytes(entry[20..24].try_into().unwrap()),
+                // resolved once the data chunks are known
+                frame: 0,
+            })
+            .unwrap();
+    }
+
+    Ok(cue_points)
+}
+
+/// collect the `labl` texts of a `LIST` chunk, other list types are ignored
+pub(super) fn parse_adtl<File: PlatformFile>(
+    file: &mut File,
+    chunk: &Chunk,
+    labels: &mut Vec<CueLabel, MAX_CUE_POINTS>,
+) -> Result<(), Error> {
+    let mut list_type = [0_u8; 4];
+    read_at(file, chunk.start, &mut list_type)?;
+    if list_type != *b"adtl" {
+        return Ok(());
+    }
+
+    let mut offset = chunk.start + 4;
+    while offset + 8 <= chunk.end && !labels.is_full() {
+        let mut header = [0_u8; 8];
+        if read_at(file, offset, &mut header)? < header.len() {
+            break;
+        }
+        let len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as u64;
+
+        if header[..4] == *b"labl" && len >= 4 {
+            let mut text = [0_u8; 4 + MAX_LABEL_LEN];
+            let text_len = (len as usize).min(text.len());
+            let read = read_at(file, offset + 8, &mut text[..text_len])?;
+            if read >= 4 {
+                let id = u32::from_le_bytes(text[0..4].try_into().unwrap());
+                labels
+                    .push(CueLabel {
+                        id,
+                        text: to_label(&text[4..read]),
+                    })
+                    .ok();
+            }
+        }
+
+        // sub chunks are word aligned like every other RIFF chunk
+        offset += 8 + len + len % 2;
+    }
+
+    Ok(())
+}
+
+/// convert null terminated text of at most `N` bytes, dropping anything that isn't valid utf8
+pub(super) fn to_label<const N: usize>(bytes: &[u8]) -> String<N> {
+    let bytes = bytes.split(|byte| *byte == 0).next().unwrap_or_default();
+    let text = match core::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => core::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap(),
+    };
+    let mut label = String::new();
+    label.push_str(text).unwrap();
+    label
+}
@@ -0,0 +1,29 @@
+use heapless::String;
+
+use super::{Chunk, Error, cue::to_label, read_at};
+use crate::PlatformFile;
+
+/// Longest display title kept from a `DISP` chunk, longer ones are truncated
+pub const MAX_DISPLAY_TITLE_LEN: usize = 64;
+
+/// Windows clipboard format of null terminated text
+const CF_TEXT: u32 = 1;
+
+/// parse a `DISP` chunk, returns `None` if it holds something other than text such as an icon
+pub(super) fn parse_disp<File: PlatformFile>(
+    file: &mut File,
+    chunk: &Chunk,
+) -> Result<Option<String<MAX_DISPLAY_TITLE_LEN>>, Error> {
+    let mut format = [0_u8; 4];
+    if chunk.data_len() < format.len() || read_at(file, chunk.start, &mut format)? < format.len() {
+        return Err(Error::ChunkSizeIncorrect);
+    }
+    if u32::from_le_bytes(format) != CF_TEXT {
+        return Ok(None);
+    }
+
+    let mut text = [0_u8; MAX_DISPLAY_TITLE_LEN];
+    let text_len = (chunk.data_len() - format.len()).min(text.len());
+    let read = read_at(file, chunk.start + 4, &mut text[..text_len])?;
+    Ok(Some(to_label(&text[..read])))
+}
@@ -0,0 +1,51 @@
+use heapless::Vec;
+
+use super::{Error, Wav};
+use crate::{AudioFile, MAX_CHANNELS, PlatformFile};
+
+/// One frame of samples converted to 16 bit, in channel order
+pub type Frame = Vec<i16, { MAX_CHANNELS as usize }>;
+
+/// Iterator over whole frames of a [`Wav`], see [`Wav::frames`]
+///
+/// ends at EOF or after the first error
+pub struct FrameIter<'a, File: PlatformFile> {
+    wav: &'a mut Wav<File>,
+    done: bool,
+}
+
+impl<'a, File: PlatformFile> FrameIter<'a, File> {
+    pub(super) fn new(wav: &'a mut Wav<File>) -> Self {
+        Self { wav, done: false }
+    }
+}
+
+impl<File: PlatformFile> Iterator for FrameIter<'_, File> {
+    type Item = Result<Frame, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut buf = [0_u8; 64];
+        match self.wav.read_frame(&mut buf) {
+            Ok(true) => {
+                let format = self.wav.sample_format();
+                let frame = buf[..self.wav.frame_size()]
+                    .chunks_exact(format.size() as usize)
+                    .map(|sample| format.to_i16(sample))
+                    .collect();
+                Some(Ok(frame))
+            }
+            Ok(false) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
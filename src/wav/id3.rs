@@ -0,0 +1,42 @@
+use heapless::String;
+
+/// Length in bytes of the text fields of an ID3v1 tag
+pub const ID3V1_FIELD_LEN: usize = 30;
+
+/// Length of an ID3v1 tag, which always sits in the last bytes of the file
+pub(super) const ID3V1_LEN: usize = 128;
+
+/// Metadata from an ID3v1 tag appended after the RIFF data
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Id3v1 {
+    pub title: String<ID3V1_FIELD_LEN>,
+    pub artist: String<ID3V1_FIELD_LEN>,
+    pub album: String<ID3V1_FIELD_LEN>,
+    pub year: String<4>,
+}
+
+/// parse the last 128 bytes of a file, `None` if they don't start with `TAG`
+pub(super) fn parse_id3v1(bytes: &[u8; ID3V1_LEN]) -> Option<Id3v1> {
+    if bytes[..3] != *b"TAG" {
+        return None;
+    }
+    Some(Id3v1 {
+        title: to_text(&bytes[3..33]),
+        artist: to_text(&bytes[33..63]),
+        album: to_text(&bytes[63..93]),
+        year: to_text(&bytes[93..97]),
+    })
+}
+
+/// convert a latin-1 field padded with nulls or spaces, characters that don't fit are dropped
+fn to_text<const N: usize>(bytes: &[u8]) -> String<N> {
+    let bytes = bytes.split(|byte| *byte == 0).next().unwrap_or_default();
+    let mut text = String::new();
+    for byte in bytes.trim_ascii_end() {
+        // latin-1 is the first 256 unicode code points
+        if text.push(char::from(*byte)).is_err() {
+            break;
+        }
+    }
+    text
+}
@@ -0,0 +1,242 @@
+use embedded_sdmmc::{
+    BlockDevice, Directory, Error as SdmmcError, File, Mode, ShortFileName, TimeSource,
+};
+use heapless::Vec;
+
+use super::{Error, Wav, WavProperties};
+
+impl<
+    'a,
+    D: BlockDevice,
+    T: TimeSource,
+    const MAX_DIRS: usize,
+    const MAX_FILES: usize,
+    const MAX_VOLUMES: usize,
+> Wav<File<'a, D, T, MAX_DIRS, MAX_FILES, MAX_VOLUMES>>
+{
+    /// parse an open sdmmc file, same as [`Wav::new`] but the volume manager's generics are
+    /// inferred from `file` so call sites don't need a turbofish
+    ///
+    /// the file is only read, so the time source is never asked for a timestamp
+    pub fn from_sdmmc_file(
+        file: File<'a, D, T, MAX_DIRS, MAX_FILES, MAX_VOLUMES>,
+    ) -> Result<Self, Error> {
+        Self::new(file)
+    }
+}
+
+/// probe every `.WAV` file in `dir` for a playlist, each file is closed once probed
+///
+/// files that aren't valid wavs are skipped, any past the first `N` are ignored
+pub fn index_directory<
+    D: BlockDevice,
+    T: TimeSource,
+    const MAX_DIRS: usize,
+    const MAX_FILES: usize,
+    const MAX_VOLUMES: usize,
+    const N: usize,
+>(
+    dir: &mut Directory<'_, D, T, MAX_DIRS, MAX_FILES, MAX_VOLUMES>,
+) -> Result<Vec<(ShortFileName, WavProperties), N>, SdmmcError<D::Error>> {
+    // files can't be opened while the directory is being iterated
+    let mut names: Vec<ShortFileName, N> = Vec::new();
+    dir.iterate_dir(|entry| {
+        if !entry.attributes.is_directory() && entry.name.extension() == b"WAV" {
+            let _ = names.push(entry.name.clone());
+        }
+    })?;
+
+    let mut index = Vec::new();
+    for name in names {
+        let mut file = dir.open_file_in_dir(&name, Mode::ReadOnly)?;
+        if let Ok(properties) = Wav::probe(&mut file) {
+            // never more entries than names
+            let _ = index.push((name, properties));
+        }
+        file.close()?;
+    }
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_sdmmc::{Block, BlockCount, BlockDevice, BlockIdx, TimeSource, Timestamp};
+    use embedded_sdmmc::{VolumeIdx, VolumeManager};
+    use std::cell::RefCell;
+
+    use super::index_directory;
+    use crate::{
+        AudioFile, Channels, MONO_16BIT, PlatformFile, PlatformFileError, STEREO_8BIT,
+        SampleFormat, wav::Wav,
+    };
+
+    // a FAT16 partition with one block per cluster, just over the minimum cluster count
+    const PARTITION_START: usize = 1;
+    const PARTITION_BLOCKS: usize = 4200;
+    const FAT_BLOCKS: usize = 17;
+    const FAT_START: usize = PARTITION_START + 1;
+    const ROOT_DIR_START: usize = FAT_START + FAT_BLOCKS;
+    const DATA_START: usize = ROOT_DIR_START + 1;
+
+    struct RamDisk(RefCell<std::vec::Vec<u8>>);
+
+    impl RamDisk {
+        fn block(&mut self, index: usize) -> &mut [u8] {
+            &mut self.0.get_mut()[index * Block::LEN..][..Block::LEN]
+        }
+
+        /// FAT16 card with the files in the root directory, one cluster each
+        fn new(files: &[(&[u8; 11], &[u8])]) -> Self {
+            let mut disk = Self(RefCell::new(std::vec![
+                0;
+                (PARTITION_START + PARTITION_BLOCKS) * Block::LEN
+            ]));
+
+            let mbr = disk.block(0);
+            mbr[446 + 4] = 0x06; // FAT16
+            mbr[446 + 8..446 + 12].copy_from_slice(&(PARTITION_START as u32).to_le_bytes());
+            mbr[446 + 12..446 + 16].copy_from_slice(&(PARTITION_BLOCKS as u32).to_le_bytes());
+            mbr[510..].copy_from_slice(&[0x55, 0xaa]);
+
+            let bpb = disk.block(PARTITION_START);
+            bpb[11..13].copy_from_slice(&(Block::LEN as u16).to_le_bytes());
+            bpb[13] = 1; // blocks per cluster
+            bpb[14..16].copy_from_slice(&1_u16.to_le_bytes()); // reserved blocks
+            bpb[16] = 1; // fat count
+            bpb[17..19].copy_from_slice(&16_u16.to_le_bytes()); // root entries
+            bpb[19..21].copy_from_slice(&(PARTITION_BLOCKS as u16).to_le_bytes());
+            bpb[21] = 0xf8; // media
+            bpb[22..24].copy_from_slice(&(FAT_BLOCKS as u16).to_le_bytes());
+            bpb[510..].copy_from_slice(&[0x55, 0xaa]);
+
+            let fat = disk.block(FAT_START);
+            fat[..4].copy_from_slice(&[0xf8, 0xff, 0xff, 0xff]);
+            for cluster in 2..2 + files.len() {
+                fat[cluster * 2..cluster * 2 + 2].copy_from_slice(&[0xff, 0xff]);
+            }
+
+            for (i, (name, data)) in files.iter().enumerate() {
+                let cluster = 2 + i;
+                let entry = &mut disk.block(ROOT_DIR_START)[i * 32..][..32];
+                entry[..11].copy_from_slice(*name);
+                entry[11] = 0x20; // archive
+                entry[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+                entry[28..32].copy_from_slice(&(data.len() as u32).to_le_bytes());
+                disk.block(DATA_START + i)[..data.len()].copy_from_slice(data);
+            }
+            disk
+        }
+    }
+
+    impl BlockDevice for RamDisk {
+        type Error = ();
+
+        fn read(&self, blocks: &mut [Block], start: BlockIdx, _reason: &str) -> Result<(), ()> {
+            let disk = self.0.borrow();
+            for (i, block) in blocks.iter_mut().enumerate() {
+                let offset = (start.0 as usize + i) * Block::LEN;
+                block
+                    .contents
+                    .copy_from_slice(disk.get(offset..offset + Block::LEN).ok_or(())?);
+            }
+            Ok(())
+        }
+
+        fn write(&self, blocks: &[Block], start: BlockIdx) -> Result<(), ()> {
+            let mut disk = self.0.borrow_mut();
+            for (i, block) in blocks.iter().enumerate() {
+                let offset = (start.0 as usize + i) * Block::LEN;
+                disk.get_mut(offset..offset + Block::LEN)
+                    .ok_or(())?
+                    .copy_from_slice(&block.contents);
+            }
+            Ok(())
+        }
+
+        fn num_blocks(&self) -> Result<BlockCount, ()> {
+            Ok(BlockCount((self.0.borrow().len() / Block::LEN) as u32))
+        }
+    }
+
+    struct Clock;
+
+    impl TimeSource for Clock {
+        fn get_timestamp(&self) -> Timestamp {
+            Timestamp::from_fat(0, 0)
+        }
+    }
+
+    #[test]
+    fn index_two_wavs() {
+        let disk = RamDisk::new(&[
+            (b"MONO    WAV", MONO_16BIT),
+            (b"README  TXT", b"not audio"),
+            (b"STEREO  WAV", STEREO_8BIT),
+        ]);
+        let mut volume_mgr = VolumeManager::new(disk, Clock);
+        let mut volume = volume_mgr.open_volume(VolumeIdx(0)).unwrap();
+        let mut root = volume.open_root_dir().unwrap();
+
+        let index = index_directory::<_, _, 4, 4, 1, 8>(&mut root).unwrap();
+        assert!(index.len() == 2);
+
+        let (name, mono) = &index[0];
+        assert!(name.base_name() == b"MONO");
+        assert!(mono.channels == Channels::Mono);
+        assert!(mono.sample_format == SampleFormat::I16);
+        assert!(mono.data_len == 8);
+
+        let (name, stereo) = &index[1];
+        assert!(name.base_name() == b"STEREO");
+        assert!(stereo.channels == Channels::Stereo);
+        assert!(stereo.sample_format == SampleFormat::U8);
+    }
+
+    #[test]
+    fn seek_past_u32() {
+        let disk = RamDisk::new(&[(b"MONO    WAV", MONO_16BIT)]);
+        let mut volume_mgr = VolumeManager::new(disk, Clock);
+        let mut volume = volume_mgr.open_volume(VolumeIdx(0)).unwrap();
+        let mut root = volume.open_root_dir().unwrap();
+        let mut file = root
+            .open_file_in_dir("MONO.WAV", embedded_sdmmc::Mode::ReadOnly)
+            .unwrap();
+
+        // these would truncate to an offset of 4
+        let offset = u32::MAX as u64 + 5;
+        assert!(
+            PlatformFile::seek_from_start(&mut file, offset)
+                == Err(PlatformFileError::SeekOutofBounds)
+        );
+        assert!(
+            PlatformFile::seek_from_end(&mut file, offset)
+                == Err(PlatformFileError::SeekOutofBounds)
+        );
+        assert!(
+            PlatformFile::seek_from_current(&mut file, offset as i64)
+                == Err(PlatformFileError::SeekOutofBounds)
+        );
+
+        // the position is unchanged
+        let mut riff = [0_u8; 4];
+        assert!(PlatformFile::read(&mut file, &mut riff) == Ok(4));
+        assert!(riff == *b"RIFF");
+    }
+
+    #[test]
+    fn from_sdmmc_file() {
+        let disk = RamDisk::new(&[(b"MONO    WAV", MONO_16BIT)]);
+        let mut volume_mgr = VolumeManager::new(disk, Clock);
+        let mut volume = volume_mgr.open_volume(VolumeIdx(0)).unwrap();
+        let mut root = volume.open_root_dir().unwrap();
+        let file = root
+            .open_file_in_dir("MONO.WAV", embedded_sdmmc::Mode::ReadOnly)
+            .unwrap();
+
+        let mut wav = Wav::from_sdmmc_file(file).unwrap();
+        assert!(wav.channels() == Channels::Mono);
+        let mut samples = [0_u8; 8];
+        assert!(wav.read(&mut samples) == Ok(8));
+        assert!(samples == MONO_16BIT[44..]);
+    }
+}
@@ -0,0 +1,243 @@
+use heapless::Vec;
+
+use super::Error;
+
+/// Predictor coefficient pairs in a MS ADPCM fmt chunk, files with more aren't supported
+pub const MS_ADPCM_COEFFICIENTS: usize = 7;
+
+/// Scales the step size by the magnitude of the last nibble
+const ADAPTATION: [i32; 16] = [
+    230, 230, 230, 230, 307, 409, 512, 614, 768, 614, 512, 409, 307, 230, 230, 230,
+];
+
+/// Block layout and predictors from the fmt extension of a MS ADPCM file
+#[derive(Clone, Debug)]
+pub(super) struct MsAdpcmFmt {
+    block_align: usize,
+    channels: usize,
+    coefficients: Vec<(i16, i16), MS_ADPCM_COEFFICIENTS>,
+}
+
+/// parse the fmt extension after cbSize, `ext` starts at `wSamplesPerBlock`
+pub(super) fn parse_ms_adpcm(
+    ext: &[u8],
+    channels: u16,
+    block_align: u16,
+) -> Result<MsAdpcmFmt, Error> {
+    if !(1..=2).contains(&channels) {
+        return Err(Error::UnsupportedChannelCount);
+    }
+    let (channels, block_align) = (channels as usize, block_align as usize);
+    let header_len = 7 * channels;
+    if block_align <= header_len || ext.len() < 4 {
+        return Err(Error::FmtChunkError);
+    }
+
+    // every byte after the block header holds two samples
+    let samples_per_block = u16::from_le_bytes([ext[0], ext[1]]) as usize;
+    if samples_per_block != 2 + (block_align - header_len) * 2 / channels {
+        return Err(Error::FmtChunkError);
+    }
+
+    let count = u16::from_le_bytes([ext[2], ext[3]]) as usize;
+    if count > MS_ADPCM_COEFFICIENTS {
+        return Err(Error::UnsupportedAudioFormat);
+    }
+    let table = ext.get(4..4 + count * 4).ok_or(Error::FmtChunkError)?;
+    let coefficients = table
+        .chunks_exact(4)
+        .map(|pair| {
+            (
+                i16::from_le_bytes([pair[0], pair[1]]),
+                i16::from_le_bytes([pair[2], pair[3]]),
+            )
+        })
+        .collect();
+
+    Ok(MsAdpcmFmt {
+        block_align,
+        channels,
+        coefficients,
+    })
+}
+
+/// Prediction state of one channel
+#[derive(Copy, Clone, Debug, Default)]
+struct Predictor {
+    coefficients: (i32, i32),
+    delta: i32,
+    sample1: i32,
+    sample2: i32,
+}
+
+impl Predictor {
+    fn decode(&mut self, nibble: u8) -> i16 {
+        // nibbles are 4 bit two's complement
+        let signed = ((nibble << 4) as i8 >> 4) as i32;
+        let predicted =
+            (self.sample1 * self.coefficients.0 + self.sample2 * self.coefficients.1) >> 8;
+        let sample = (predicted + signed * self.delta).clamp(i16::MIN as i32, i16::MAX as i32);
+
+        self.sample2 = self.sample1;
+        self.sample1 = sample;
+        self.delta = ((ADAPTATION[nibble as usize] * self.delta) >> 8).max(16);
+        sample as i16
+    }
+}
+
+/// Decodes MS ADPCM blocks a byte at a time so no block sized buffer is needed
+#[derive(Clone, Debug)]
+pub(super) struct MsAdpcm {
+    fmt: MsAdpcmFmt,
+    predictors: [Predictor; 2],
+    /// decoded samples not handed out yet, in output order
+    pending: [i16; 4],
+    pending_len: usize,
+    pending_pos: usize,
+}
+
+impl MsAdpcm {
+    pub(super) fn new(fmt: MsAdpcmFmt) -> Self {
+        Self {
+            fmt,
+            predictors: [Predictor::default(); 2],
+            pending: [0; 4],
+            pending_len: 0,
+            pending_pos: 0,
+        }
+    }
+
+    pub(super) fn block_align(&self) -> usize {
+        self.fmt.block_align
+    }
+
+    /// bytes of predictor state at the start of every block
+    pub(super) fn header_len(&self) -> usize {
+        7 * self.fmt.channels
+    }
+
+    /// number of samples `encoded` bytes of blocks decode to
+    pub(super) fn decoded_samples(&self, encoded: u64) -> u64 {
+        let block_align = self.fmt.block_align as u64;
+        let header_len = self.header_len() as u64;
+        let channels = self.fmt.channels as u64;
+        let per_block = 2 * channels + (block_align - header_len) * 2;
+
+        let partial = match encoded % block_align {
+            len if len >= header_len => 2 * channels + (len - header_len) * 2,
+            _ => 0,
+        };
+        encoded / block_align * per_block + partial
+    }
+
+    /// encoded offset of the block holding decoded `sample`
+    pub(super) fn block_start(&self, sample: u64) -> u64 {
+        let channels = self.fmt.channels as u64;
+        let per_block = 2 * channels + (self.fmt.block_align - self.header_len()) as u64 * 2;
+        sample / per_block * self.fmt.block_align as u64
+    }
+
+    /// load the predictors from a block header, its first two samples become pending
+    pub(super) fn start_block(&mut self, header: &[u8]) -> Result<(), Error> {
+        let channels = self.fmt.channels;
+        let field = |index: usize, channel: usize| {
+            let offset = channels + (index * channels + channel) * 2;
+            i16::from_le_bytes([header[offset], header[offset + 1]]) as i32
+        };
+
+        self.clear();
+        for (channel, predictor) in header[..channels].iter().enumerate() {
+            let coefficients = *self
+                .fmt
+                .coefficients
+                .get(*predictor as usize)
+                .ok_or(Error::AdpcmBlockError)?;
+            self.predictors[channel] = Predictor {
+                coefficients: (coefficients.0 as i32, coefficients.1 as i32),
+                delta: field(0, channel),
+                sample1: field(1, channel),
+                sample2: field(2, channel),
+            };
+        }
+
+        // the older sample comes first
+        for channel in 0..channels {
+            self.push(self.predictors[channel].sample2 as i16);
+        }
+        for channel in 0..channels {
+            self.push(self.predictors[channel].sample1 as i16);
+        }
+        Ok(())
+    }
+
+    /// decode the two samples of one byte after the block header, high nibble first
+    pub(super) fn decode_byte(&mut self, byte: u8) {
+        self.clear();
+        let high = self.predictors[0].decode(byte >> 4);
+        self.push(high);
+        let low = self.predictors[self.fmt.channels - 1].decode(byte & 0x0f);
+        self.push(low);
+    }
+
+    pub(super) fn pop(&mut self) -> Option<i16> {
+        if self.pending_pos == self.pending_len {
+            return None;
+        }
+        self.pending_pos += 1;
+        Some(self.pending[self.pending_pos - 1])
+    }
+
+    /// number of decoded samples not handed out yet
+    pub(super) fn pending(&self) -> usize {
+        self.pending_len - self.pending_pos
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.pending_len = 0;
+        self.pending_pos = 0;
+    }
+
+    fn push(&mut self, sample: i16) {
+        self.pending[self.pending_len] = sample;
+        self.pending_len += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MsAdpcm, parse_ms_adpcm};
+
+    /// samples per block, coefficient count and the standard coefficient table
+    const EXTENSION: &[u8] = &[
+        0x06, 0x00, 0x07, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0xff, 0x00, 0x00, 0x00,
+        0x00, 0xc0, 0x00, 0x40, 0x00, 0xf0, 0x00, 0x00, 0x00, 0xcc, 0x01, 0x30, 0xff, 0x88, 0x01,
+        0x18, 0xff,
+    ];
+
+    #[test]
+    fn decode_block() {
+        let mut adpcm = MsAdpcm::new(parse_ms_adpcm(EXTENSION, 1, 9).unwrap());
+        assert!(adpcm.decoded_samples(18) == 12);
+        assert!(adpcm.decoded_samples(17) == 10);
+
+        // predictor 1, delta 32, sample1 -200, sample2 -100
+        adpcm
+            .start_block(&[0x01, 0x20, 0x00, 0x38, 0xff, 0x9c, 0xff])
+            .unwrap();
+        let mut samples = [0_i16; 6];
+        for (i, byte) in [0x7a, 0x81].into_iter().enumerate() {
+            if i == 0 {
+                samples[0] = adpcm.pop().unwrap();
+                samples[1] = adpcm.pop().unwrap();
+            }
+            adpcm.decode_byte(byte);
+            samples[2 + i * 2] = adpcm.pop().unwrap();
+            samples[3 + i * 2] = adpcm.pop().unwrap();
+        }
+        assert!(adpcm.pop().is_none());
+        assert!(samples == [-100, -200, -76, -408, -1956, -3048]);
+
+        // the block size doesn't match the samples per block
+        assert!(parse_ms_adpcm(EXTENSION, 1, 10).is_err());
+    }
+}
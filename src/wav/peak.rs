@@ -0,0 +1,58 @@
+use heapless::Vec;
+
+use super::{Chunk, Error, read_at};
+use crate::{MAX_CHANNELS, PlatformFile};
+
+/// Loudest sample of one channel from the `PEAK` chunk
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Peak {
+    /// absolute peak value, 1.0 is full scale
+    pub value: f32,
+    /// frame the peak occurs at
+    pub position: u32,
+}
+
+/// Contents of a `PEAK` chunk, commonly found in IEEE float files
+#[derive(PartialEq, Clone, Debug)]
+pub struct PeakInfo {
+    pub version: u32,
+    /// seconds since 1970-01-01 when the peaks were measured
+    pub timestamp: u32,
+    /// one peak per channel, in channel order
+    pub peaks: Vec<Peak, { MAX_CHANNELS as usize }>,
+}
+
+/// parse a `PEAK` chunk holding one entry per channel
+pub(super) fn parse_peak<File: PlatformFile>(
+    file: &mut File,
+    chunk: &Chunk,
+    channels: u16,
+) -> Result<PeakInfo, Error> {
+    let mut header = [0_u8; 8];
+    if read_at(file, chunk.start, &mut header)? < header.len() {
+        return Err(Error::ChunkSizeIncorrect);
+    }
+
+    let mut peaks = Vec::new();
+    let mut entry = [0_u8; 8];
+    for offset in (chunk.start + 8..chunk.end)
+        .step_by(entry.len())
+        .take((channels as usize).min(MAX_CHANNELS as usize))
+    {
+        if read_at(file, offset, &mut entry)? < entry.len() {
+            break;
+        }
+        peaks
+            .push(Peak {
+                value: f32::from_le_bytes(entry[0..4].try_into().unwrap()),
+                position: u32::from_le_bytes(entry[4..8].try_into().unwrap()),
+            })
+            .unwrap();
+    }
+
+    Ok(PeakInfo {
+        version: u32::from_le_bytes(header[0..4].try_into().unwrap()),
+        timestamp: u32::from_le_bytes(header[4..8].try_into().unwrap()),
+        peaks,
+    })
+}
@@ -0,0 +1,86 @@
+use core::marker::PhantomData;
+
+use super::Error;
+use crate::{AudioFile, Channels, PlatformFile, SampleFormat};
+
+/// Samples of a [`Wav`](super::Wav) held in RAM, see [`Wav::load_to_ram`](super::Wav::load_to_ram)
+///
+/// plays and restarts without touching the file it was loaded from
+pub struct RamWav<File: PlatformFile, const N: usize> {
+    data: [u8; N],
+    len: usize,
+    pos: usize,
+    sample_rate: u16,
+    channels: Channels,
+    sample_format: SampleFormat,
+    _file: PhantomData<File>,
+}
+
+impl<File: PlatformFile, const N: usize> RamWav<File, N> {
+    pub(super) fn new(
+        data: [u8; N],
+        len: usize,
+        sample_rate: u16,
+        channels: Channels,
+        sample_format: SampleFormat,
+    ) -> Self {
+        Self {
+            data,
+            len,
+            pos: 0,
+            sample_rate,
+            channels,
+            sample_format,
+            _file: PhantomData,
+        }
+    }
+
+    /// get the samples, in the format they were read in
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+}
+
+impl<File: PlatformFile, const N: usize> AudioFile<File> for RamWav<File, N> {
+    type Error = Error;
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let len = buf.len().min(self.len - self.pos);
+        buf[..len].copy_from_slice(&self.data[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+
+    fn sample_rate(&self) -> u16 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> Channels {
+        self.channels
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.sample_format
+    }
+
+    fn try_seek(&mut self, sample_offset: i64) -> Result<(), Error> {
+        let target = self.pos as i64 + sample_offset * self.sample_format.size() as i64;
+        if target < 0 || target > self.len as i64 {
+            return Err(Error::PositionOutOfBounds);
+        }
+        self.pos = target as usize;
+        Ok(())
+    }
+
+    fn played(&self) -> usize {
+        self.pos / self.sample_format.size() as usize
+    }
+
+    fn data_len(&self) -> usize {
+        self.len
+    }
+
+    fn is_eof(&self) -> bool {
+        self.pos >= self.len
+    }
+}